@@ -0,0 +1,105 @@
+/* Posts a paste, reads it back with its comments and deletes it again.
+ *
+ * Build against the library, e.g. after `cargo build --release`:
+ *   cc -std=c99 -Iinclude examples/c/pbcli_example.c -Ltarget/release -lpbcli -o pbcli_example
+ *   LD_LIBRARY_PATH=target/release ./pbcli_example https://privatebin.net/
+ */
+#include <stdio.h>
+
+#include "pbcli.h"
+
+static int fail(const char *what, PbcliStatus status) {
+  const char *message = pbcli_last_error_message();
+  fprintf(stderr, "%s failed (%d): %s\n", what, (int)status, message ? message : "unknown error");
+  return 1;
+}
+
+int main(int argc, char **argv) {
+  PbcliClient *client = NULL;
+  PbcliPasteOptions *options = NULL;
+  PbcliPostResult *posted = NULL;
+  PbcliPaste *paste = NULL;
+  PbcliDecryptedPaste *decrypted = NULL;
+  PbcliComments *comments = NULL;
+  char *comment_id = NULL;
+  PbcliStatus status;
+  int result = 1;
+
+  if (argc != 2) {
+    fprintf(stderr, "usage: %s <privatebin url>\n", argv[0]);
+    return 2;
+  }
+  printf("pbcli %s\n", pbcli_version());
+
+  status = pbcli_client_new(argv[1], &client);
+  if (status != PBCLI_STATUS_OK) {
+    return fail("creating the client", status);
+  }
+  pbcli_client_set_timeout(client, 10);
+
+  options = pbcli_paste_options_new();
+  pbcli_paste_options_set_expire(options, "1day");
+  pbcli_paste_options_set_discussion(options, true);
+
+  status = pbcli_post_paste(client, "Hello from C", NULL, options, &posted);
+  if (status != PBCLI_STATUS_OK) {
+    result = fail("posting the paste", status);
+    goto out;
+  }
+  printf("posted %s\n", pbcli_post_result_paste_url(posted));
+
+  status = pbcli_post_comment(client, pbcli_post_result_paste_id(posted),
+                              pbcli_post_result_key(posted), NULL, NULL, "c example",
+                              "A comment from C", &comment_id);
+  if (status != PBCLI_STATUS_OK) {
+    result = fail("posting a comment", status);
+    goto out;
+  }
+  printf("commented %s\n", comment_id);
+
+  status = pbcli_get_paste(client, pbcli_post_result_paste_id(posted), &paste);
+  if (status != PBCLI_STATUS_OK) {
+    result = fail("fetching the paste", status);
+    goto out;
+  }
+  status = pbcli_paste_decrypt(paste, pbcli_post_result_key(posted), NULL, &decrypted);
+  if (status != PBCLI_STATUS_OK) {
+    result = fail("decrypting the paste", status);
+    goto out;
+  }
+  printf("text: %s\n", pbcli_decrypted_paste_text(decrypted));
+
+  status = pbcli_paste_decrypt_comments(paste, pbcli_post_result_key(posted), NULL, &comments);
+  if (status != PBCLI_STATUS_OK) {
+    result = fail("decrypting the comments", status);
+    goto out;
+  }
+  for (size_t i = 0; i < pbcli_comments_count(comments); i++) {
+    PbcliComment comment;
+    if (pbcli_comments_get(comments, i, &comment) != PBCLI_STATUS_OK) {
+      continue;
+    }
+    printf("%*s%s: %s\n", (int)comment.depth * 2, "",
+           comment.nickname ? comment.nickname : "anonymous",
+           comment.text ? comment.text : comment.error);
+  }
+
+  status = pbcli_delete_paste(client, pbcli_post_result_paste_id(posted),
+                              pbcli_post_result_delete_token(posted));
+  if (status != PBCLI_STATUS_OK) {
+    result = fail("deleting the paste", status);
+    goto out;
+  }
+  printf("deleted\n");
+  result = 0;
+
+out:
+  pbcli_string_free(comment_id);
+  pbcli_comments_free(comments);
+  pbcli_decrypted_paste_free(decrypted);
+  pbcli_paste_free(paste);
+  pbcli_post_result_free(posted);
+  pbcli_paste_options_free(options);
+  pbcli_client_free(client);
+  return result;
+}
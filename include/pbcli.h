@@ -0,0 +1,456 @@
+/* C interface of pbcli, declared in src/capi.rs. After changing it, regenerate with
+ * cbindgen --config cbindgen.toml --output include/pbcli.h */
+
+#ifndef PBCLI_H
+#define PBCLI_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of every fallible function. Details are available from `pbcli_last_error_message`.
+ */
+typedef enum PbcliStatus {
+  PBCLI_STATUS_OK = 0,
+  /**
+   * A required pointer was NULL, a string was not valid UTF-8 or a value was out of range
+   */
+  PBCLI_STATUS_INVALID_ARGUMENT = 1,
+  /**
+   * The server could not be reached
+   */
+  PBCLI_STATUS_NETWORK = 2,
+  /**
+   * The server rejected the request or sent something unexpected
+   */
+  PBCLI_STATUS_SERVER = 3,
+  /**
+   * The paste does not exist, has expired or has been deleted
+   */
+  PBCLI_STATUS_NOT_FOUND = 4,
+  /**
+   * Wrong key or password, or the paste is damaged
+   */
+  PBCLI_STATUS_DECRYPTION = 5,
+  /**
+   * The paste exceeds the size limit of the client or the instance
+   */
+  PBCLI_STATUS_SIZE_LIMIT = 6,
+  /**
+   * The instance does not offer the requested expiry, format or feature
+   */
+  PBCLI_STATUS_UNSUPPORTED = 7,
+  /**
+   * Obtaining an oidc access token failed
+   */
+  PBCLI_STATUS_AUTH = 8,
+  PBCLI_STATUS_IO = 9,
+  /**
+   * A bug in pbcli, please report it
+   */
+  PBCLI_STATUS_PANIC = 98,
+  PBCLI_STATUS_OTHER = 99,
+} PbcliStatus;
+
+/**
+ * Format of a new paste, passed to `pbcli_paste_options_set_format` as an integer
+ */
+typedef enum PbcliFormat {
+  PBCLI_FORMAT_PLAINTEXT = 0,
+  PBCLI_FORMAT_SYNTAX = 1,
+  PBCLI_FORMAT_MARKDOWN = 2,
+  /**
+   * Detect the format from the paste text
+   */
+  PBCLI_FORMAT_AUTO = 3,
+} PbcliFormat;
+
+/**
+ * Connection to a PrivateBin instance
+ */
+typedef struct PbcliClient PbcliClient;
+
+/**
+ * Settings of a new paste
+ */
+typedef struct PbcliPasteOptions PbcliPasteOptions;
+
+/**
+ * A posted paste
+ */
+typedef struct PbcliPostResult PbcliPostResult;
+
+/**
+ * A fetched, still encrypted paste with its comments
+ */
+typedef struct PbcliPaste PbcliPaste;
+
+/**
+ * Decrypted text and attachment of a paste
+ */
+typedef struct PbcliDecryptedPaste PbcliDecryptedPaste;
+
+/**
+ * Comments of a paste, in thread order
+ */
+typedef struct PbcliComments PbcliComments;
+
+/**
+ * A comment borrowed from `PbcliComments`, valid until the comments are freed
+ */
+typedef struct PbcliComment {
+  const char *id;
+  /**
+   * Id of the comment this one replies to, or the paste id
+   */
+  const char *parent_id;
+  /**
+   * NULL if the comment has no nickname or could not be decrypted
+   */
+  const char *nickname;
+  /**
+   * NULL if the comment could not be decrypted
+   */
+  const char *text;
+  /**
+   * Why the comment could not be decrypted, NULL if it was decrypted
+   */
+  const char *error;
+  /**
+   * Unix time the comment was created, 0 if unknown
+   */
+  int64_t created;
+  /**
+   * Nesting level in its thread, 0 for comments on the paste itself
+   */
+  uint32_t depth;
+} PbcliComment;
+
+#ifdef __cplusplus
+extern "C" {
+#endif  // __cplusplus
+
+/**
+ * Version of the library, a static string
+ */
+const char *pbcli_version(void);
+
+/**
+ * Message of the last failed call on this thread, NULL if there was none.
+ * The string stays valid until the next failing call on the same thread.
+ */
+const char *pbcli_last_error_message(void);
+
+/**
+ * Free a string returned by pbcli. Passing NULL is allowed.
+ *
+ * # Safety
+ * `value` must be NULL or a string returned by pbcli which has not been freed yet.
+ */
+void pbcli_string_free(char *value);
+
+/**
+ * Create a client for the instance at `url`
+ *
+ * # Safety
+ * `url` must be a NUL terminated string and `out` must point to writable memory.
+ */
+PbcliStatus pbcli_client_new(const char *url, PbcliClient **out);
+
+/**
+ * Set the connection timeout, requests may take four times as long
+ *
+ * # Safety
+ * `client` must be a client returned by `pbcli_client_new`.
+ */
+PbcliStatus pbcli_client_set_timeout(PbcliClient *client, uint64_t seconds);
+
+/**
+ * Accept invalid TLS certificates
+ *
+ * # Safety
+ * `client` must be a client returned by `pbcli_client_new`.
+ */
+PbcliStatus pbcli_client_set_insecure(PbcliClient *client, bool insecure);
+
+/**
+ * Trust the certificates in a PEM bundle in addition to the system ones
+ *
+ * # Safety
+ * `client` must be a client returned by `pbcli_client_new` and `path` a NUL terminated string.
+ */
+PbcliStatus pbcli_client_set_ca_cert(PbcliClient *client, const char *path);
+
+/**
+ * Authenticate with an access token obtained from an oidc token endpoint
+ *
+ * # Safety
+ * `client` must be a client returned by `pbcli_client_new`, the other arguments
+ * NUL terminated strings.
+ */
+PbcliStatus pbcli_client_set_oidc(PbcliClient *client,
+                                  const char *token_url,
+                                  const char *client_id,
+                                  const char *username,
+                                  const char *password);
+
+/**
+ * Free a client. Passing NULL is allowed.
+ *
+ * # Safety
+ * `client` must be NULL or a client returned by `pbcli_client_new` which has not been freed yet.
+ */
+void pbcli_client_free(PbcliClient *client);
+
+/**
+ * Default settings for a new paste: plaintext, expiring after a week,
+ * without burn after reading and discussion
+ */
+PbcliPasteOptions *pbcli_paste_options_new(void);
+
+/**
+ * Set the expiry, one of the values offered by the instance such as "1day"
+ *
+ * # Safety
+ * `options` must be returned by `pbcli_paste_options_new` and `expire` a NUL terminated string.
+ */
+PbcliStatus pbcli_paste_options_set_expire(PbcliPasteOptions *options, const char *expire);
+
+/**
+ * Set the format to one of the `PbcliFormat` values
+ *
+ * # Safety
+ * `options` must be returned by `pbcli_paste_options_new`.
+ */
+PbcliStatus pbcli_paste_options_set_format(PbcliPasteOptions *options, uint32_t format);
+
+/**
+ * # Safety
+ * `options` must be returned by `pbcli_paste_options_new`.
+ */
+PbcliStatus pbcli_paste_options_set_burn(PbcliPasteOptions *options, bool burn);
+
+/**
+ * # Safety
+ * `options` must be returned by `pbcli_paste_options_new`.
+ */
+PbcliStatus pbcli_paste_options_set_discussion(PbcliPasteOptions *options, bool discussion);
+
+/**
+ * Fail with `PBCLI_STATUS_SIZE_LIMIT` if the encrypted paste is larger, 0 removes the limit
+ *
+ * # Safety
+ * `options` must be returned by `pbcli_paste_options_new`.
+ */
+PbcliStatus pbcli_paste_options_set_size_limit(PbcliPasteOptions *options, uint64_t bytes);
+
+/**
+ * Free paste options. Passing NULL is allowed.
+ *
+ * # Safety
+ * `options` must be NULL or returned by `pbcli_paste_options_new` and not freed yet.
+ */
+void pbcli_paste_options_free(PbcliPasteOptions *options);
+
+/**
+ * Encrypt and post a paste
+ *
+ * `password` and `options` may be NULL for no password and default options.
+ *
+ * # Safety
+ * `client` must be a client returned by `pbcli_client_new`, `text` and `password`
+ * NUL terminated strings, `options` returned by `pbcli_paste_options_new` and `out`
+ * must point to writable memory.
+ */
+PbcliStatus pbcli_post_paste(const PbcliClient *client,
+                             const char *text,
+                             const char *password,
+                             const PbcliPasteOptions *options,
+                             PbcliPostResult **out);
+
+/**
+ * Id of the posted paste, valid until the result is freed
+ *
+ * # Safety
+ * `result` must be returned by `pbcli_post_paste` and not freed yet.
+ */
+const char *pbcli_post_result_paste_id(const PbcliPostResult *result);
+
+/**
+ * Key to decrypt the posted paste, valid until the result is freed
+ *
+ * # Safety
+ * `result` must be returned by `pbcli_post_paste` and not freed yet.
+ */
+const char *pbcli_post_result_key(const PbcliPostResult *result);
+
+/**
+ * Url of the posted paste including its key, valid until the result is freed
+ *
+ * # Safety
+ * `result` must be returned by `pbcli_post_paste` and not freed yet.
+ */
+const char *pbcli_post_result_paste_url(const PbcliPostResult *result);
+
+/**
+ * Url which deletes the posted paste, valid until the result is freed
+ *
+ * # Safety
+ * `result` must be returned by `pbcli_post_paste` and not freed yet.
+ */
+const char *pbcli_post_result_delete_url(const PbcliPostResult *result);
+
+/**
+ * Token for `pbcli_delete_paste`, valid until the result is freed
+ *
+ * # Safety
+ * `result` must be returned by `pbcli_post_paste` and not freed yet.
+ */
+const char *pbcli_post_result_delete_token(const PbcliPostResult *result);
+
+/**
+ * Free a post result. Passing NULL is allowed.
+ *
+ * # Safety
+ * `result` must be NULL or returned by `pbcli_post_paste` and not freed yet.
+ */
+void pbcli_post_result_free(PbcliPostResult *result);
+
+/**
+ * Fetch a paste with its comments. Burn after reading pastes are deleted by fetching them.
+ *
+ * # Safety
+ * `client` must be a client returned by `pbcli_client_new`, `paste_id` a NUL terminated
+ * string and `out` must point to writable memory.
+ */
+PbcliStatus pbcli_get_paste(const PbcliClient *client, const char *paste_id, PbcliPaste **out);
+
+/**
+ * Decrypt a fetched paste. `password` may be NULL if the paste has none.
+ *
+ * # Safety
+ * `paste` must be returned by `pbcli_get_paste`, `key` and `password` NUL terminated
+ * strings and `out` must point to writable memory.
+ */
+PbcliStatus pbcli_paste_decrypt(const PbcliPaste *paste,
+                                const char *key,
+                                const char *password,
+                                PbcliDecryptedPaste **out);
+
+/**
+ * Text of the paste, valid until the decrypted paste is freed
+ *
+ * # Safety
+ * `paste` must be returned by `pbcli_paste_decrypt` and not freed yet.
+ */
+const char *pbcli_decrypted_paste_text(const PbcliDecryptedPaste *paste);
+
+/**
+ * Attachment as data url, NULL if the paste has none
+ *
+ * # Safety
+ * `paste` must be returned by `pbcli_paste_decrypt` and not freed yet.
+ */
+const char *pbcli_decrypted_paste_attachment(const PbcliDecryptedPaste *paste);
+
+/**
+ * File name of the attachment, NULL if the paste has none
+ *
+ * # Safety
+ * `paste` must be returned by `pbcli_paste_decrypt` and not freed yet.
+ */
+const char *pbcli_decrypted_paste_attachment_name(const PbcliDecryptedPaste *paste);
+
+/**
+ * Free a decrypted paste, its text is overwritten. Passing NULL is allowed.
+ *
+ * # Safety
+ * `paste` must be NULL or returned by `pbcli_paste_decrypt` and not freed yet.
+ */
+void pbcli_decrypted_paste_free(PbcliDecryptedPaste *paste);
+
+/**
+ * Decrypt the comments of a fetched paste. A comment which fails to decrypt does not fail
+ * the call, its `error` is set instead. `password` may be NULL if the paste has none.
+ *
+ * # Safety
+ * `paste` must be returned by `pbcli_get_paste`, `key` and `password` NUL terminated
+ * strings and `out` must point to writable memory.
+ */
+PbcliStatus pbcli_paste_decrypt_comments(const PbcliPaste *paste,
+                                         const char *key,
+                                         const char *password,
+                                         PbcliComments **out);
+
+/**
+ * Number of comments, 0 if `comments` is NULL
+ *
+ * # Safety
+ * `comments` must be NULL or returned by `pbcli_paste_decrypt_comments` and not freed yet.
+ */
+size_t pbcli_comments_count(const PbcliComments *comments);
+
+/**
+ * Comment at `index`. Replies follow the comment they reply to.
+ *
+ * # Safety
+ * `comments` must be returned by `pbcli_paste_decrypt_comments` and not freed yet,
+ * `out` must point to writable memory.
+ */
+PbcliStatus pbcli_comments_get(const PbcliComments *comments, size_t index, PbcliComment *out);
+
+/**
+ * Free comments, their text is overwritten. Passing NULL is allowed.
+ *
+ * # Safety
+ * `comments` must be NULL or returned by `pbcli_paste_decrypt_comments` and not freed yet.
+ */
+void pbcli_comments_free(PbcliComments *comments);
+
+/**
+ * Free a fetched paste. Passing NULL is allowed.
+ *
+ * # Safety
+ * `paste` must be NULL or returned by `pbcli_get_paste` and not freed yet.
+ */
+void pbcli_paste_free(PbcliPaste *paste);
+
+/**
+ * Encrypt and post a comment on a paste
+ *
+ * `parent_id` may be NULL to comment on the paste itself, `password` if the paste has none
+ * and `nickname` to post anonymously. `comment_id` may be NULL if the id of the new comment
+ * is not needed, otherwise it receives a string to free with `pbcli_string_free`.
+ *
+ * # Safety
+ * `client` must be a client returned by `pbcli_client_new`, the strings NUL terminated
+ * and `comment_id` NULL or pointing to writable memory.
+ */
+PbcliStatus pbcli_post_comment(const PbcliClient *client,
+                               const char *paste_id,
+                               const char *key,
+                               const char *password,
+                               const char *parent_id,
+                               const char *nickname,
+                               const char *text,
+                               char **comment_id);
+
+/**
+ * Delete a paste with the token returned when it was posted
+ *
+ * # Safety
+ * `client` must be a client returned by `pbcli_client_new`, `paste_id` and
+ * `delete_token` NUL terminated strings.
+ */
+PbcliStatus pbcli_delete_paste(const PbcliClient *client,
+                               const char *paste_id,
+                               const char *delete_token);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PBCLI_H */
@@ -1,32 +1,73 @@
+use crate::cache::{CachedCapabilities, CapabilityCache};
+use crate::capabilities::InstanceCapabilities;
+use crate::client::{ClientConfig, CommentOptions, OidcAuth, PasteOptions};
 use crate::crypto::{encrypt, Decryptable};
 use crate::error::{PasteError, PbError, PbResult};
-use crate::opts::Opts;
-use crate::privatebin::{Comment, DecryptedComment, Paste, PostCommentResponse, PostPasteResponse};
-use crate::util::check_filesize;
+use crate::privatebin::{
+    Comment, DecryptedComment, Paste, PasteFormat, PostCommentResponse, PostPasteResponse,
+};
+use crate::progress::{ProgressListener, ProgressPhase, ProgressReader};
+use crate::secret::SecretString;
+use crate::util::{check_filesize, SizeLimitConfirmation};
 use crate::DecryptedPaste;
 use rand_chacha::rand_core::{RngCore, SeedableRng};
+use reqwest::header::{HeaderValue, AUTHORIZATION};
 use reqwest::tls::Certificate;
 use reqwest::{Method, Url};
-use scraper::{Html, Selector};
+use std::io::Read;
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Duration;
+use std::sync::Arc;
+use zeroize::Zeroizing;
 
 #[cfg_attr(feature = "uniffi", derive(uniffi::Object))]
 pub struct API {
     base: Url,
-    opts: Opts,
+    config: ClientConfig,
+    progress: Option<Arc<dyn ProgressListener>>,
+    size_confirmation: Option<Arc<dyn SizeLimitConfirmation>>,
 }
 
 #[cfg_attr(feature = "uniffi", uniffi::export)]
 impl API {
     #[cfg_attr(feature = "uniffi", uniffi::constructor)]
-    pub fn new(mut url: Url, opts: Opts) -> Self {
+    pub fn new(mut url: Url, config: ClientConfig) -> Self {
         url.set_fragment(None);
         url.set_query(None);
         if !url.path().ends_with('/') {
             url.set_path(&format!("{}{}", url.path(), "/"))
         }
-        Self { base: url, opts }
+        Self {
+            base: url,
+            config,
+            progress: None,
+            size_confirmation: None,
+        }
+    }
+
+    /// Same as `new`, but reports progress of encryption and network transfers to the listener
+    #[cfg_attr(feature = "uniffi", uniffi::constructor)]
+    pub fn new_with_progress(
+        url: Url,
+        config: ClientConfig,
+        listener: Arc<dyn ProgressListener>,
+    ) -> Self {
+        Self::new_with_callbacks(url, config, Some(listener), None)
+    }
+
+    /// Same as `new`, with optional progress listener and a callback asked whether to post
+    /// pastes and comments which exceed the size limit. Without it they fail.
+    #[cfg_attr(feature = "uniffi", uniffi::constructor)]
+    pub fn new_with_callbacks(
+        url: Url,
+        config: ClientConfig,
+        progress: Option<Arc<dyn ProgressListener>>,
+        size_confirmation: Option<Arc<dyn SizeLimitConfirmation>>,
+    ) -> Self {
+        let mut api = Self::new(url, config);
+        api.progress = progress;
+        api.size_confirmation = size_confirmation;
+        api
     }
 }
 
@@ -34,16 +75,14 @@ impl API {
     fn build_client(&self) -> PbResult<reqwest::blocking::Client> {
         let mut builder = reqwest::blocking::Client::builder();
 
-        let timeout_secs = self.opts.timeout.unwrap_or(30);
-        builder = builder
-            .connect_timeout(Duration::from_secs(timeout_secs))
-            .timeout(Duration::from_secs(timeout_secs * 4));
+        let timeout = self.config.timeout_or_default();
+        builder = builder.connect_timeout(timeout).timeout(timeout * 4);
 
-        if self.opts.insecure {
+        if self.config.insecure {
             builder = builder.danger_accept_invalid_certs(true);
         }
 
-        if let Some(ref ca_path) = self.opts.ca_cert {
+        if let Some(ref ca_path) = self.config.ca_cert {
             let pem = std::fs::read(ca_path).map_err(|e| {
                 PbError::InvalidCertificate(format!(
                     "failed to read CA cert {}: {}",
@@ -59,20 +98,15 @@ impl API {
         Ok(builder.build()?)
     }
 
-    fn get_oidc_access_token(&self) -> PbResult<String> {
-        let oidc_token_endpoint = self.opts.oidc_token_url.as_ref().unwrap();
-        let oidc_client_id = self.opts.oidc_client_id.as_ref().unwrap();
-        let oidc_username = self.opts.oidc_username.as_ref().unwrap();
-        let oidc_password = self.opts.oidc_password.as_ref().unwrap();
-
+    fn get_oidc_access_token(&self, auth: &OidcAuth) -> PbResult<SecretString> {
         let mut post_fields = std::collections::HashMap::new();
         post_fields.insert("grant_type", "password");
-        post_fields.insert("client_id", oidc_client_id);
-        post_fields.insert("username", oidc_username);
-        post_fields.insert("password", oidc_password);
+        post_fields.insert("client_id", auth.client_id.as_str());
+        post_fields.insert("username", auth.username.as_str());
+        post_fields.insert("password", auth.password.expose_secret());
 
         let client = self.build_client()?;
-        let mut request = client.post(oidc_token_endpoint);
+        let mut request = client.post(&auth.token_url);
         request = request.form(&post_fields);
 
         let response = request.send()?;
@@ -85,21 +119,18 @@ impl API {
 
         let token_type = access_token_response
             .get("token_type")
-            .unwrap()
-            .as_str()
-            .unwrap();
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| PbError::OidcInvalidResponse("missing token_type".into()))?;
         if !token_type.eq_ignore_ascii_case("bearer") {
             return Err(PbError::InvalidTokenType(token_type.to_string()));
         }
 
-        let token: String = access_token_response
+        let token = access_token_response
             .get("access_token")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| PbError::OidcInvalidResponse("missing access_token".into()))?;
 
-        Ok(token)
+        Ok(SecretString::from(token))
     }
 
     fn preconfigured_privatebin_request_builder(
@@ -115,14 +146,132 @@ impl API {
             request = request.header("X-Requested-With", "JSONHttpRequest");
         }
 
-        if self.opts.oidc_token_url.is_some() {
-            let access_token = self.get_oidc_access_token()?;
-            let auth_header = ["Bearer".into(), access_token].join(" ");
-            request = request.header("Authorization", auth_header)
+        if let Some(auth) = &self.config.oidc {
+            let access_token = self.get_oidc_access_token(auth)?;
+            let auth_header = Zeroizing::new(format!("Bearer {}", access_token.expose_secret()));
+            let mut auth_header = HeaderValue::from_str(&auth_header).map_err(|_| {
+                PbError::OidcInvalidResponse("access_token is not a valid header value".into())
+            })?;
+            auth_header.set_sensitive(true);
+            request = request.header(AUTHORIZATION, auth_header)
         }
 
         Ok(request)
     }
+
+    fn capability_cache_path(&self) -> Option<PathBuf> {
+        if !self.config.capabilities_cache {
+            return None;
+        }
+        CapabilityCache::default_path()
+    }
+
+    /// Scrape features and limits from the front page of the instance
+    fn fetch_capabilities(&self) -> PbResult<InstanceCapabilities> {
+        let url = self.base.clone();
+        let response = self
+            .preconfigured_privatebin_request_builder("GET", url, false)?
+            .send()?;
+        response.error_for_status_ref()?;
+        let html = response.text()?;
+        InstanceCapabilities::from_html(&html)
+    }
+
+    /// Capabilities used to validate a request before sending it.
+    /// Without `probe` only a fresh cache entry is used and the front page is not requested.
+    /// Failing to query them is not fatal, the server still validates the request.
+    fn capabilities_for_validation(&self, probe: bool) -> Option<InstanceCapabilities> {
+        if !probe {
+            return self
+                .cached_capabilities()
+                .filter(|entry| entry.is_fresh(self.config.capabilities_ttl_secs()))
+                .map(|entry| entry.capabilities);
+        }
+        match self.capabilities() {
+            Ok(capabilities) => Some(capabilities),
+            Err(err) => {
+                log::debug!("could not query instance capabilities, skipping validation: {err}");
+                None
+            }
+        }
+    }
+
+    fn send_with_progress(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+        body: String,
+    ) -> PbResult<reqwest::blocking::Response> {
+        let len = body.len() as u64;
+        let reader = ProgressReader::new(
+            std::io::Cursor::new(body.into_bytes()),
+            self.progress.clone(),
+            ProgressPhase::Upload,
+            Some(len),
+        );
+        let body = reqwest::blocking::Body::sized(reader, len);
+        Ok(request.body(body).send()?)
+    }
+
+    fn read_with_progress(&self, response: reqwest::blocking::Response) -> PbResult<Vec<u8>> {
+        let total = response.content_length();
+        let mut reader = ProgressReader::new(
+            response,
+            self.progress.clone(),
+            ProgressPhase::Download,
+            total,
+        );
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+/// Parse a json response of the privatebin api.
+/// Returns the response if its status is 0, otherwise the error reported by the server.
+/// `fallback` is returned if the server reports an error without a message.
+fn parse_privatebin_response(
+    http_status: u16,
+    body: &[u8],
+    fallback: PasteError,
+) -> PbResult<serde_json::Value> {
+    let value: serde_json::Value = match serde_json::from_slice(body) {
+        Ok(value) => value,
+        Err(_) => return Err(unexpected_response(http_status, body)),
+    };
+
+    match value.get("status").and_then(|s| s.as_u64()) {
+        Some(0) => Ok(value),
+        Some(1) => match value.get("message").and_then(|m| m.as_str()) {
+            Some(message) => Err(PasteError::ServerError {
+                http_status,
+                message: message.to_string(),
+            }),
+            None => Err(fallback),
+        },
+        Some(status) => Err(PasteError::UnknownPasteStatus(status as u32)),
+        None => Err(unexpected_response(http_status, body)),
+    }
+}
+
+fn unexpected_response(http_status: u16, body: &[u8]) -> PasteError {
+    const MAX_EXCERPT_CHARS: usize = 200;
+    let text = String::from_utf8_lossy(body);
+    PasteError::UnexpectedResponse {
+        http_status,
+        excerpt: text.trim().chars().take(MAX_EXCERPT_CHARS).collect(),
+    }
+}
+
+/// Whether an instance could refuse a paste with these settings because of its configuration.
+/// Plaintext pastes with the default expiry and no optional features are accepted everywhere.
+fn may_be_rejected(options: &PasteOptions, password: bool, attachment: bool) -> bool {
+    options.auto_format
+        || options.format != PasteFormat::Plaintext
+        || options.expire != PasteOptions::default().expire
+        || options.discussion
+        || options.burn
+        || password
+        || attachment
 }
 
 fn pem_certs_from_bundle(pem: &[u8]) -> PbResult<Vec<Certificate>> {
@@ -156,69 +305,103 @@ fn pem_certs_from_bundle(pem: &[u8]) -> PbResult<Vec<Certificate>> {
 impl API {
     pub fn get_paste(&self, paste_id: &str) -> PbResult<Paste> {
         let url = reqwest::Url::parse_with_params(self.base.as_str(), [("pasteid", paste_id)])?;
-        let value: serde_json::Value = self
+        let response = self
             .preconfigured_privatebin_request_builder("GET", url, true)?
-            .send()?
-            .json()?;
-        let status: u32 = value.get("status").unwrap().as_u64().unwrap() as u32;
-
-        match status {
-            0 => Ok(serde_json::from_value(value)?),
-            1 => Err(PasteError::PasteNotFound),
-            s => Err(PasteError::UnknownPasteStatus(s)),
-        }
+            .send()?;
+        let http_status = response.status().as_u16();
+        let body = self.read_with_progress(response)?;
+        // reading a paste only fails if it does not exist, has expired or has been deleted
+        let value = parse_privatebin_response(http_status, &body, PasteError::PasteNotFound(None))
+            .map_err(|err| match err {
+                PasteError::ServerError { message, .. } => PasteError::PasteNotFound(Some(message)),
+                err => err,
+            })?;
+        Ok(serde_json::from_value(value)?)
     }
 
     pub fn post_paste(
         &self,
         content: &DecryptedPaste,
         password: &str,
-        opts: &Opts,
+        options: &PasteOptions,
     ) -> PbResult<PostPasteResponse> {
+        let has_password = !password.is_empty();
+        let capabilities = self.capabilities_for_validation(may_be_rejected(
+            options,
+            has_password,
+            content.attachment.is_some(),
+        ));
+        let mut format = options.paste_format(content);
+        if options.auto_format {
+            // a guess should never make the post fail, fall back to plaintext
+            let supported = capabilities
+                .as_ref()
+                .is_none_or(|c| c.formats.is_empty() || c.formats.contains(&format));
+            if !supported {
+                format = PasteFormat::Plaintext;
+            }
+            log::debug!("detected paste format {format:?}");
+        }
+        if let Some(capabilities) = &capabilities {
+            capabilities.validate(
+                format,
+                &options.expire,
+                options.discussion,
+                options.burn,
+                has_password,
+                content.attachment.is_some(),
+            )?;
+        }
+
         let mut rng = rand_chacha::ChaCha20Rng::from_entropy();
-        let mut paste_passphrase = [0u8; 32];
-        rng.fill_bytes(&mut paste_passphrase);
+        let mut paste_passphrase = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(paste_passphrase.as_mut());
 
         let mut paste = Paste {
             v: 2,
             ..Default::default()
         };
-        paste.adata.format = opts.format;
-        paste.adata.discuss = opts.discussion as u8;
-        paste.adata.burn = opts.burn as u8;
-        paste.meta.expire = Some(opts.expire.clone());
+        paste.adata.cipher = options.cipher()?;
+        paste.adata.format = format;
+        paste.adata.discuss = options.discussion as u8;
+        paste.adata.burn = options.burn as u8;
+        paste.meta.expire = Some(options.expire.clone());
 
         let cipher = &paste.adata.cipher;
 
+        let plaintext = Zeroizing::new(serde_json::to_string(content)?);
         let encrypted_content = encrypt(
-            &serde_json::to_string(content)?,
-            &paste_passphrase,
+            &plaintext,
+            paste_passphrase.as_ref(),
             password,
-            &cipher.vec_kdf_salt()?,
-            &cipher.vec_cipher_iv()?,
-            cipher.kdf_iterations,
+            cipher,
             &paste.get_adata_str(),
+            options.compression_level,
+            self.progress.as_deref(),
         )?;
 
         let b64_encrpyed_content = base64::encode(encrypted_content);
-        check_filesize(b64_encrpyed_content.len() as u64, opts.size_limit);
+        check_filesize(
+            b64_encrpyed_content.len() as u64,
+            options.size_limit,
+            self.size_confirmation.as_deref(),
+        )?;
         paste.ct = b64_encrpyed_content;
 
         let url = self.base.clone();
-        let response = self
-            .preconfigured_privatebin_request_builder("POST", url, true)?
-            .body::<String>(serde_json::to_string(&paste).unwrap())
-            .send()?;
-        let mut rsv: serde_json::Value = response.json()?;
-        rsv["bs58key"] = serde_json::Value::String(bs58::encode(paste_passphrase).into_string());
-        rsv["baseurl"] = serde_json::Value::String(self.base.to_string());
-        let status: u32 = rsv.get("status").unwrap().as_u64().unwrap() as u32;
-
-        match status {
-            0 => Ok(serde_json::from_value::<PostPasteResponse>(rsv)?),
-            1 => Err(PasteError::InvalidData),
-            s => Err(PasteError::UnknownPasteStatus(s)),
+        let body = serde_json::to_string(&paste)?;
+        if let Some(capabilities) = &capabilities {
+            capabilities.validate_size(body.len() as u64)?;
         }
+        let request = self.preconfigured_privatebin_request_builder("POST", url, true)?;
+        let response = self.send_with_progress(request, body)?;
+        let http_status = response.status().as_u16();
+        let mut rsv =
+            parse_privatebin_response(http_status, &response.bytes()?, PasteError::InvalidData)?;
+        rsv["bs58key"] =
+            serde_json::Value::String(bs58::encode(paste_passphrase.as_ref()).into_string());
+        rsv["baseurl"] = serde_json::Value::String(self.base.to_string());
+        Ok(serde_json::from_value::<PostPasteResponse>(rsv)?)
     }
 
     pub fn post_comment(
@@ -228,65 +411,243 @@ impl API {
         parent_id: &str,
         bs58key: &str,
         password: &str,
-        opts: &Opts,
+        options: &CommentOptions,
     ) -> PbResult<PostCommentResponse> {
+        let capabilities = self.capabilities_for_validation(true);
+        if let Some(capabilities) = &capabilities {
+            capabilities.validate_comment()?;
+        }
+
         let mut comment = Comment {
             v: 2,
             pasteid: paste_id.into(),
             parentid: parent_id.into(),
+            adata: options.cipher()?,
             ..Default::default()
         };
         let cipher = &comment.adata;
-        let paste_passphrase = bs58::decode(bs58key).into_vec()?;
+        let paste_passphrase = Zeroizing::new(bs58::decode(bs58key).into_vec()?);
 
+        let plaintext = Zeroizing::new(serde_json::to_string(content)?);
         let encrypted_content = encrypt(
-            &serde_json::to_string(content)?,
-            &paste_passphrase,
+            &plaintext,
+            paste_passphrase.as_ref(),
             password,
-            &cipher.vec_kdf_salt()?,
-            &cipher.vec_cipher_iv()?,
-            cipher.kdf_iterations,
+            cipher,
             &comment.get_adata_str(),
+            options.compression_level,
+            self.progress.as_deref(),
         )?;
 
         let b64_encrpyed_content = base64::encode(encrypted_content);
-        check_filesize(b64_encrpyed_content.len() as u64, opts.size_limit);
+        check_filesize(
+            b64_encrpyed_content.len() as u64,
+            options.size_limit,
+            self.size_confirmation.as_deref(),
+        )?;
         comment.ct = b64_encrpyed_content;
 
         let url = self.base.clone();
-        let response = self
-            .preconfigured_privatebin_request_builder("POST", url, true)?
-            .body::<String>(serde_json::to_string(&comment).unwrap())
-            .send()?;
-        let rsv: serde_json::Value = response.json()?;
-        let status: u32 = rsv.get("status").unwrap().as_u64().unwrap() as u32;
-
-        match status {
-            0 => Ok(serde_json::from_value::<PostCommentResponse>(rsv)?),
-            1 => Err(PasteError::InvalidData),
-            s => Err(PasteError::UnknownPasteStatus(s)),
+        let body = serde_json::to_string(&comment)?;
+        if let Some(capabilities) = &capabilities {
+            capabilities.validate_size(body.len() as u64)?;
         }
+        let request = self.preconfigured_privatebin_request_builder("POST", url, true)?;
+        let response = self.send_with_progress(request, body)?;
+        let http_status = response.status().as_u16();
+        let rsv =
+            parse_privatebin_response(http_status, &response.bytes()?, PasteError::InvalidData)?;
+        Ok(serde_json::from_value::<PostCommentResponse>(rsv)?)
     }
 
-    pub fn scrape_expiries(&self) -> PbResult<Vec<String>> {
+    /// Delete a paste with the delete token returned when it was posted
+    pub fn delete_paste(&self, paste_id: &str, delete_token: &str) -> PbResult<()> {
         let url = self.base.clone();
+        let body = serde_json::json!({ "pasteid": paste_id, "deletetoken": delete_token });
         let response = self
-            .preconfigured_privatebin_request_builder("GET", url, false)?
+            .preconfigured_privatebin_request_builder("POST", url, true)?
+            .body(body.to_string())
             .send()?;
-        response.error_for_status_ref()?;
-        let html = response.text()?;
-        let document = Html::parse_document(&html);
-        let expiries_selector = Selector::parse("#expiration + ul > li > a").unwrap();
-        let mut expiries = Vec::new();
-        for expiry_anchor in document.select(&expiries_selector) {
-            if let Some(expiry) = expiry_anchor.attr("data-expiration") {
-                expiries.push(expiry.to_string());
+        let http_status = response.status().as_u16();
+        parse_privatebin_response(http_status, &response.bytes()?, PasteError::InvalidData)?;
+        Ok(())
+    }
+
+    pub fn scrape_expiries(&self) -> PbResult<Vec<String>> {
+        Ok(self.capabilities()?.expiries)
+    }
+
+    /// Query features and limits of the instance.
+    /// Uses the capability cache if it holds a fresh entry for this instance,
+    /// and falls back to a stale entry if the instance cannot be reached.
+    pub fn capabilities(&self) -> PbResult<InstanceCapabilities> {
+        let Some(path) = self.capability_cache_path() else {
+            return self.fetch_capabilities();
+        };
+        let mut cache = CapabilityCache::load(&path);
+        let key = self.base.as_str();
+        let ttl = self.config.capabilities_ttl_secs();
+
+        if let Some(entry) = cache.get(key) {
+            if entry.is_fresh(ttl) {
+                log::debug!("using cached capabilities for {key}");
+                return Ok(entry.capabilities.clone());
+            }
+        }
+
+        match self.fetch_capabilities() {
+            Ok(capabilities) => {
+                cache.insert(key, CachedCapabilities::new(capabilities.clone()));
+                if let Err(err) = cache.save() {
+                    log::debug!("failed to save capability cache: {err}");
+                }
+                Ok(capabilities)
             }
+            Err(err) => match cache.get(key) {
+                Some(entry) => {
+                    log::warn!(
+                        "could not query capabilities ({err}), using cached ones from {}s ago",
+                        entry.age()
+                    );
+                    Ok(entry.capabilities.clone())
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    /// Query features and limits of the instance, ignoring any cached entry
+    pub fn refresh_capabilities(&self) -> PbResult<InstanceCapabilities> {
+        let capabilities = self.fetch_capabilities()?;
+        if let Some(path) = self.capability_cache_path() {
+            let mut cache = CapabilityCache::load(&path);
+            cache.insert(
+                self.base.as_str(),
+                CachedCapabilities::new(capabilities.clone()),
+            );
+            cache.save()?;
         }
-        Ok(expiries)
+        Ok(capabilities)
+    }
+
+    /// Cached capabilities of the instance, regardless of their age
+    pub fn cached_capabilities(&self) -> Option<CachedCapabilities> {
+        let path = self.capability_cache_path()?;
+        CapabilityCache::load(&path)
+            .get(self.base.as_str())
+            .cloned()
     }
 
     pub fn base(&self) -> Url {
         self.base.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// Answer a single request with `status` and `body`, returns the request body
+    fn serve_once(status: &'static str, body: &'static str) -> (Url, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut request = vec![0; content_length];
+            reader.read_exact(&mut request).unwrap();
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (url, server)
+    }
+
+    fn delete(status: &'static str, body: &'static str) -> (PbResult<()>, String) {
+        let (url, server) = serve_once(status, body);
+        let result =
+            API::new(url, ClientConfig::default()).delete_paste("f37ca34e72e2ef77", "token");
+        (result, server.join().unwrap())
+    }
+
+    #[test]
+    fn get_paste_reports_missing_pastes() {
+        let (url, server) = serve_once(
+            "200 OK",
+            r#"{"status":1,"message":"Paste does not exist, has expired or has been deleted."}"#,
+        );
+        let result = API::new(url, ClientConfig::default()).get_paste("f37ca34e72e2ef77");
+        server.join().unwrap();
+        assert!(matches!(
+            result,
+            Err(PasteError::PasteNotFound(Some(message))) if message.starts_with("Paste does not exist")
+        ));
+
+        let (url, server) = serve_once("502 Bad Gateway", "<html>Bad gateway</html>");
+        let result = API::new(url, ClientConfig::default()).get_paste("f37ca34e72e2ef77");
+        server.join().unwrap();
+        assert!(matches!(
+            result,
+            Err(PasteError::UnexpectedResponse {
+                http_status: 502,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn delete_paste_sends_id_and_token() {
+        let (result, request) = delete("200 OK", r#"{"status":0,"id":"f37ca34e72e2ef77"}"#);
+        result.unwrap();
+        let request: serde_json::Value = serde_json::from_str(&request).unwrap();
+        assert_eq!(
+            request,
+            serde_json::json!({ "pasteid": "f37ca34e72e2ef77", "deletetoken": "token" })
+        );
+    }
+
+    #[test]
+    fn delete_paste_reports_the_server_message() {
+        let (result, _) = delete(
+            "200 OK",
+            r#"{"status":1,"message":"Wrong deletion token. Paste was not deleted."}"#,
+        );
+        assert!(matches!(
+            result,
+            Err(PasteError::ServerError { message, .. }) if message.contains("Wrong deletion token")
+        ));
+    }
+
+    #[test]
+    fn delete_paste_reports_unexpected_pages() {
+        let (result, _) = delete("502 Bad Gateway", "<html><h1>502 Bad Gateway</h1></html>");
+        assert!(matches!(
+            result,
+            Err(PasteError::UnexpectedResponse { http_status: 502, excerpt })
+                if excerpt.starts_with("<html>")
+        ));
+
+        let (result, _) = delete("200 OK", r#"{"status":1}"#);
+        assert!(matches!(result, Err(PasteError::InvalidData)));
+    }
+}
@@ -0,0 +1,132 @@
+use pbcli::{PasteError, PbResult};
+use rand_core::{OsRng, RngCore};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// File written to a temporary file next to its path first and renamed into place,
+/// so an interrupted write never leaves a truncated file behind.
+/// The temporary file is removed if the file is dropped without being committed.
+pub struct AtomicFile {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    file: Option<File>,
+}
+
+impl AtomicFile {
+    /// Create the temporary file, fails if path exists and overwrite is not set.
+    /// A private file is only readable by the owner, others get the umask default.
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    pub fn create(path: &Path, overwrite: bool, private: bool) -> PbResult<Self> {
+        if path.exists() && !overwrite {
+            return Err(PasteError::FileExists);
+        }
+        let file_name = path.file_name().ok_or(PasteError::NotAFile)?;
+        // a unique name, a temporary file left behind by a crash never blocks a save
+        let mut suffix = [0u8; 8];
+        OsRng.fill_bytes(&mut suffix);
+        let suffix: String = suffix.iter().map(|b| format!("{b:02x}")).collect();
+        let tmp_path = path.with_file_name(format!(
+            ".{}.{suffix}.pbcli-tmp",
+            file_name.to_string_lossy()
+        ));
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        if private {
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        }
+        let file = options.open(&tmp_path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            tmp_path,
+            file: Some(file),
+        })
+    }
+
+    /// Write data to the temporary file and rename it into place
+    pub fn commit(mut self, data: &[u8]) -> PbResult<()> {
+        let mut file = self.file.take().expect("file is only taken on commit");
+        file.write_all(data)?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&self.tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        // the rename did not happen if commit failed or was never called
+        if self.tmp_path.exists() {
+            let _ = std::fs::remove_file(&self.tmp_path);
+        }
+    }
+}
+
+/// Write a file at once, see `AtomicFile`
+pub fn write_atomically(path: &Path, data: &[u8], overwrite: bool, private: bool) -> PbResult<()> {
+    AtomicFile::create(path, overwrite, private)?.commit(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TestDir;
+
+    #[test]
+    fn overwrite() {
+        let dir = TestDir::new("atomic-overwrite");
+        let path = dir.0.join("paste.txt");
+        write_atomically(&path, b"first", false, false).unwrap();
+        assert!(matches!(
+            write_atomically(&path, b"second", false, false),
+            Err(PasteError::FileExists)
+        ));
+        assert_eq!(std::fs::read(&path).unwrap(), b"first");
+
+        write_atomically(&path, b"second", true, false).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(dir.file_names(), ["paste.txt"]);
+    }
+
+    #[test]
+    fn stale_temporary_files_do_not_block_saving() {
+        let dir = TestDir::new("atomic-stale");
+        let path = dir.0.join("paste.txt");
+        std::fs::write(dir.0.join(".paste.txt.pbcli-tmp"), b"stale").unwrap();
+
+        write_atomically(&path, b"paste", false, false).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"paste");
+        assert_eq!(dir.file_names(), [".paste.txt.pbcli-tmp", "paste.txt"]);
+    }
+
+    #[test]
+    fn dropping_removes_the_temporary_file() {
+        let dir = TestDir::new("atomic-drop");
+        let file = AtomicFile::create(&dir.0.join("paste.txt"), false, false).unwrap();
+        assert_eq!(dir.file_names().len(), 1);
+        drop(file);
+        assert!(dir.file_names().is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn private_files_are_only_readable_by_the_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TestDir::new("atomic-permissions");
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let private = dir.0.join("private.txt");
+        write_atomically(&private, b"paste", false, true).unwrap();
+        assert_eq!(mode(&private), 0o600);
+
+        let public = dir.0.join("public.txt");
+        write_atomically(&public, b"paste", false, false).unwrap();
+        let default = dir.0.join("default.txt");
+        std::fs::write(&default, b"paste").unwrap();
+        assert_eq!(mode(&public), mode(&default));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::capabilities::InstanceCapabilities;
+use crate::error::PbResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_CAPABILITIES_TTL: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct CachedCapabilities {
+    /// unix timestamp in seconds
+    pub fetched_at: u64,
+    pub capabilities: InstanceCapabilities,
+}
+
+impl CachedCapabilities {
+    pub fn new(capabilities: InstanceCapabilities) -> Self {
+        Self {
+            fetched_at: now(),
+            capabilities,
+        }
+    }
+
+    pub fn age(&self) -> u64 {
+        now().saturating_sub(self.fetched_at)
+    }
+
+    pub fn is_fresh(&self, ttl: u64) -> bool {
+        self.age() < ttl
+    }
+}
+
+/// Capabilities of instances, keyed by base url and persisted as json
+#[derive(Debug, Default)]
+pub struct CapabilityCache {
+    path: PathBuf,
+    entries: BTreeMap<String, CachedCapabilities>,
+}
+
+impl CapabilityCache {
+    /// `capabilities.json` in the users cache directory
+    pub fn default_path() -> Option<PathBuf> {
+        let project_dirs = directories::ProjectDirs::from("eu", "mydayyy", env!("CARGO_PKG_NAME"))?;
+        Some(project_dirs.cache_dir().join("capabilities.json"))
+    }
+
+    /// Load the cache. A missing or unreadable file results in an empty cache.
+    pub fn load(path: &Path) -> Self {
+        let entries = match std::fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|err| {
+                log::debug!(
+                    "ignoring invalid capability cache {}: {err}",
+                    path.display()
+                );
+                BTreeMap::new()
+            }),
+            Err(_) => {
+                log::debug!("no capability cache at {}", path.display());
+                BTreeMap::new()
+            }
+        };
+        Self {
+            path: path.to_path_buf(),
+            entries,
+        }
+    }
+
+    pub fn get(&self, base: &str) -> Option<&CachedCapabilities> {
+        self.entries.get(base)
+    }
+
+    pub fn insert(&mut self, base: &str, entry: CachedCapabilities) {
+        self.entries.insert(base.to_string(), entry);
+    }
+
+    pub fn entries(&self) -> &BTreeMap<String, CachedCapabilities> {
+        &self.entries
+    }
+
+    /// Write the cache to disk, replacing the previous file atomically
+    pub fn save(&self) -> PbResult<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec_pretty(&self.entries)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pbcli-cache-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("nested").join("capabilities.json")
+    }
+
+    fn capabilities(version: &str) -> InstanceCapabilities {
+        InstanceCapabilities {
+            version: Some(version.into()),
+            size_limit: Some(10 * 1024 * 1024),
+            expiries: vec!["1day".into(), "1week".into()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = cache_path("roundtrip");
+        let mut cache = CapabilityCache::load(&path);
+        assert!(cache.entries().is_empty());
+
+        cache.insert(
+            "https://a.example/",
+            CachedCapabilities::new(capabilities("1.7.1")),
+        );
+        cache.insert(
+            "https://b.example/",
+            CachedCapabilities::new(capabilities("1.6.0")),
+        );
+        // creates the missing cache directory
+        cache.save().unwrap();
+
+        let loaded = CapabilityCache::load(&path);
+        assert_eq!(loaded.entries().len(), 2);
+        let entry = loaded.get("https://a.example/").unwrap();
+        assert_eq!(entry.capabilities.version.as_deref(), Some("1.7.1"));
+        assert_eq!(entry.capabilities.expiries, ["1day", "1week"]);
+        assert!(loaded.get("https://c.example/").is_none());
+        assert!(!path.with_extension("json.tmp").exists());
+        std::fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let mut entry = CachedCapabilities::new(capabilities("1.7.1"));
+        assert!(entry.is_fresh(60));
+        assert!(!entry.is_fresh(0));
+
+        entry.fetched_at -= 120;
+        assert!(entry.age() >= 120);
+        assert!(!entry.is_fresh(60));
+        assert!(entry.is_fresh(DEFAULT_CAPABILITIES_TTL));
+
+        // a clock which went backwards does not underflow
+        entry.fetched_at = now() + 3600;
+        assert_eq!(entry.age(), 0);
+    }
+
+    #[test]
+    fn corrupt_cache_files_are_ignored() {
+        let path = cache_path("corrupt");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"{ not json").unwrap();
+
+        let mut cache = CapabilityCache::load(&path);
+        assert!(cache.entries().is_empty());
+
+        // and replaced on the next save
+        cache.insert(
+            "https://a.example/",
+            CachedCapabilities::new(capabilities("1.7.1")),
+        );
+        cache.save().unwrap();
+        assert_eq!(CapabilityCache::load(&path).entries().len(), 1);
+        std::fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,229 @@
+use crate::error::{PasteError, PbResult};
+use crate::privatebin::PasteFormat;
+use clap::ValueEnum;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+
+/// Features and limits of a PrivateBin instance, as advertised on its front page
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct InstanceCapabilities {
+    pub version: Option<String>,
+    pub size_limit: Option<u64>,
+    pub expiries: Vec<String>,
+    pub formats: Vec<PasteFormat>,
+    pub discussion: bool,
+    pub burn: bool,
+    pub password: bool,
+    pub file_upload: bool,
+}
+
+impl InstanceCapabilities {
+    /// Extract capabilities from the html of the instance front page
+    pub fn from_html(html: &str) -> PbResult<Self> {
+        let document = Html::parse_document(html);
+
+        let mut expiries = select_attr(&document, "#expiration + ul > li > a", "data-expiration");
+        if expiries.is_empty() {
+            expiries = select_attr(&document, "#pasteExpiration > option", "value");
+        }
+
+        let mut format_names = select_attr(&document, "#formatter + ul > li > a", "data-format");
+        if format_names.is_empty() {
+            format_names = select_attr(&document, "#pasteFormatter > option", "value");
+        }
+        let formats: Vec<PasteFormat> = format_names
+            .into_iter()
+            .filter_map(|name| serde_json::from_value(serde_json::Value::String(name)).ok())
+            .collect();
+
+        let version = select_attr(&document, "script[src*=\"privatebin.js\"]", "src")
+            .into_iter()
+            .find_map(|src| src.split_once('?').map(|(_, v)| v.to_string()))
+            .filter(|v| !v.is_empty());
+
+        if expiries.is_empty() && formats.is_empty() && version.is_none() {
+            return Err(PasteError::UnrecognizedInstance);
+        }
+
+        let size_limit = select_attr(&document, "input[name=\"MAX_FILE_SIZE\"]", "value")
+            .into_iter()
+            .find_map(|v| v.parse().ok());
+
+        Ok(Self {
+            version,
+            size_limit,
+            expiries,
+            formats,
+            discussion: has_element(&document, "#opendiscussion"),
+            burn: has_element(&document, "#burnafterreading"),
+            password: has_element(&document, "#passwordinput"),
+            file_upload: has_element(&document, "#file"),
+        })
+    }
+
+    /// Check paste settings against the instance capabilities.
+    /// Lists which could not be scraped are not enforced.
+    pub fn validate(
+        &self,
+        format: PasteFormat,
+        expire: &str,
+        discussion: bool,
+        burn: bool,
+        password: bool,
+        attachment: bool,
+    ) -> PbResult<()> {
+        if !self.expiries.is_empty() && !self.expiries.iter().any(|e| e == expire) {
+            return Err(PasteError::UnsupportedExpiry {
+                expire: expire.to_string(),
+                supported: self.expiries.clone(),
+            });
+        }
+        if !self.formats.is_empty() && !self.formats.contains(&format) {
+            let name = format
+                .to_possible_value()
+                .map(|v| v.get_name().to_string())
+                .unwrap_or_default();
+            return Err(PasteError::UnsupportedFormat(name));
+        }
+        if discussion && !self.discussion {
+            return Err(PasteError::FeatureDisabled("discussion".into()));
+        }
+        if burn && !self.burn {
+            return Err(PasteError::FeatureDisabled("burn after reading".into()));
+        }
+        if password && !self.password {
+            return Err(PasteError::FeatureDisabled("password protection".into()));
+        }
+        if attachment && !self.file_upload {
+            return Err(PasteError::FeatureDisabled("file upload".into()));
+        }
+        Ok(())
+    }
+
+    /// Check whether comments can be posted on the instance
+    pub fn validate_comment(&self) -> PbResult<()> {
+        if !self.discussion {
+            return Err(PasteError::FeatureDisabled("discussion".into()));
+        }
+        Ok(())
+    }
+
+    /// Check the size of the request body against the size limit, if known
+    pub fn validate_size(&self, size: u64) -> PbResult<()> {
+        match self.size_limit {
+            Some(limit) if size > limit => Err(PasteError::PasteTooLarge { size, limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn select_attr(document: &Html, selector: &str, attr: &str) -> Vec<String> {
+    let selector = Selector::parse(selector).unwrap();
+    document
+        .select(&selector)
+        .filter_map(|element| element.attr(attr))
+        .map(|value| value.to_string())
+        .collect()
+}
+
+fn has_element(document: &Html, selector: &str) -> bool {
+    let selector = Selector::parse(selector).unwrap();
+    document.select(&selector).next().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOTSTRAP_PAGE: &str = include_str!("../tests/fixtures/privatebin-bootstrap.html");
+    const PAGE_TEMPLATE: &str = include_str!("../tests/fixtures/privatebin-page.html");
+
+    #[test]
+    fn from_html_bootstrap_template() {
+        let capabilities = InstanceCapabilities::from_html(BOOTSTRAP_PAGE).unwrap();
+        assert_eq!(capabilities.version.as_deref(), Some("1.7.6"));
+        assert_eq!(capabilities.size_limit, Some(10485760));
+        assert_eq!(
+            capabilities.expiries,
+            ["5min", "10min", "1hour", "1day", "1week", "1month", "1year", "never"]
+        );
+        assert_eq!(
+            capabilities.formats,
+            [
+                PasteFormat::Plaintext,
+                PasteFormat::Syntax,
+                PasteFormat::Markdown
+            ]
+        );
+        assert!(capabilities.discussion);
+        assert!(capabilities.burn);
+        assert!(capabilities.password);
+        assert!(capabilities.file_upload);
+    }
+
+    #[test]
+    fn from_html_page_template_with_disabled_features() {
+        let capabilities = InstanceCapabilities::from_html(PAGE_TEMPLATE).unwrap();
+        assert_eq!(capabilities.version.as_deref(), Some("1.4.0"));
+        assert_eq!(capabilities.size_limit, None);
+        assert_eq!(capabilities.expiries, ["5min", "1day", "1week", "never"]);
+        assert_eq!(
+            capabilities.formats,
+            [PasteFormat::Plaintext, PasteFormat::Syntax]
+        );
+        assert!(!capabilities.discussion);
+        assert!(capabilities.burn);
+        assert!(!capabilities.password);
+        assert!(!capabilities.file_upload);
+    }
+
+    #[test]
+    fn from_html_rejects_other_pages() {
+        let result = InstanceCapabilities::from_html("<html><body>Bad gateway</body></html>");
+        assert!(matches!(result, Err(PasteError::UnrecognizedInstance)));
+    }
+
+    #[test]
+    fn validate_against_page() {
+        let capabilities = InstanceCapabilities::from_html(PAGE_TEMPLATE).unwrap();
+        let validate = |format, expire, discussion, password| {
+            capabilities.validate(format, expire, discussion, false, password, false)
+        };
+
+        assert!(validate(PasteFormat::Syntax, "1day", false, false).is_ok());
+        assert!(matches!(
+            validate(PasteFormat::Plaintext, "1month", false, false),
+            Err(PasteError::UnsupportedExpiry { .. })
+        ));
+        assert!(matches!(
+            validate(PasteFormat::Markdown, "1week", false, false),
+            Err(PasteError::UnsupportedFormat(name)) if name == "markdown"
+        ));
+        assert!(matches!(
+            validate(PasteFormat::Plaintext, "1week", true, false),
+            Err(PasteError::FeatureDisabled(_))
+        ));
+        assert!(matches!(
+            validate(PasteFormat::Plaintext, "1week", false, true),
+            Err(PasteError::FeatureDisabled(_))
+        ));
+        assert!(matches!(
+            capabilities.validate_comment(),
+            Err(PasteError::FeatureDisabled(_))
+        ));
+    }
+
+    #[test]
+    fn validate_size() {
+        let capabilities = InstanceCapabilities::from_html(BOOTSTRAP_PAGE).unwrap();
+        assert!(capabilities.validate_size(10485760).is_ok());
+        assert!(matches!(
+            capabilities.validate_size(10485761),
+            Err(PasteError::PasteTooLarge { .. })
+        ));
+        assert!(InstanceCapabilities::default()
+            .validate_size(u64::MAX)
+            .is_ok());
+    }
+}
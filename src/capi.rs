@@ -0,0 +1,1160 @@
+// C interface of the cdylib and staticlib targets, declared in include/pbcli.h.
+// Regenerate the header with `cbindgen --config cbindgen.toml --output include/pbcli.h`
+// after changing this file.
+
+use crate::api::API;
+use crate::client::{ClientConfig, CommentOptions, OidcAuth, PasteOptions};
+use crate::crypto::DecryptionLimits;
+use crate::error::PasteError;
+use crate::privatebin::{
+    CommentNode, DecryptedComment, DecryptedPaste, Paste, PasteFormat, PostPasteResponse,
+};
+use crate::secret::SecretString;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::time::Duration;
+use url::Url;
+use zeroize::Zeroize;
+
+/// Result of every fallible function. Details are available from `pbcli_last_error_message`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PbcliStatus {
+    Ok = 0,
+    /// A required pointer was NULL, a string was not valid UTF-8 or a value was out of range
+    InvalidArgument = 1,
+    /// The server could not be reached
+    Network = 2,
+    /// The server rejected the request or sent something unexpected
+    Server = 3,
+    /// The paste does not exist, has expired or has been deleted
+    NotFound = 4,
+    /// Wrong key or password, or the paste is damaged
+    Decryption = 5,
+    /// The paste exceeds the size limit of the client or the instance
+    SizeLimit = 6,
+    /// The instance does not offer the requested expiry, format or feature
+    Unsupported = 7,
+    /// Obtaining an oidc access token failed
+    Auth = 8,
+    Io = 9,
+    /// A bug in pbcli, please report it
+    Panic = 98,
+    Other = 99,
+}
+
+impl From<&PasteError> for PbcliStatus {
+    fn from(err: &PasteError) -> Self {
+        match err {
+            PasteError::MissingDecryptionKey
+            | PasteError::MissingPasteId
+            | PasteError::ParseError(_)
+            | PasteError::InvalidCipherParameters(_) => PbcliStatus::InvalidArgument,
+            PasteError::Request(_) | PasteError::InvalidCertificate(_) => PbcliStatus::Network,
+            PasteError::ServerError { .. }
+            | PasteError::UnexpectedResponse { .. }
+            | PasteError::UnknownPasteStatus(_)
+            | PasteError::InvalidData
+            | PasteError::Json(_)
+            | PasteError::UnrecognizedInstance => PbcliStatus::Server,
+            PasteError::PasteNotFound(_) => PbcliStatus::NotFound,
+            PasteError::CipherNotImplemented { .. }
+            | PasteError::Aes(_)
+            | PasteError::Zlib(_)
+            | PasteError::Base58Error(_)
+            | PasteError::Base64Error(_)
+            | PasteError::KdfIterationsExceeded { .. }
+            | PasteError::DecompressedSizeExceeded { .. }
+            | PasteError::CommentDecryption { .. }
+            | PasteError::InvalidAttachment(_)
+            | PasteError::InvalidAttachmentData(_) => PbcliStatus::Decryption,
+            PasteError::SizeLimitExceeded { .. } | PasteError::PasteTooLarge { .. } => {
+                PbcliStatus::SizeLimit
+            }
+            PasteError::UnsupportedExpiry { .. }
+            | PasteError::UnsupportedFormat(_)
+            | PasteError::FeatureDisabled(_) => PbcliStatus::Unsupported,
+            PasteError::OidcBadRequest(_)
+            | PasteError::OidcInvalidResponse(_)
+            | PasteError::InvalidTokenType(_)
+            | PasteError::MissingOption(_) => PbcliStatus::Auth,
+            PasteError::Io(_) | PasteError::FileExists | PasteError::NotAFile => PbcliStatus::Io,
+            // only raised by the command line client
+            PasteError::Yaml(_)
+            | PasteError::UnknownTemplatePreset(_)
+            | PasteError::QrCode(_)
+            | PasteError::UnsupportedQrFileType(_)
+            | PasteError::EditorFailed(_)
+            | PasteError::EditorAborted
+            | PasteError::EmptyBody(_)
+            | PasteError::CommandFailed(_)
+            | PasteError::InvalidRedactRule(_)
+            | PasteError::SensitiveContent(_)
+            | PasteError::WatchBurnAfterReading
+            | PasteError::WatchMayBurn
+            | PasteError::WatchNoDiscussion
+            | PasteError::BurnAfterReading
+            | PasteError::VerifyMayBurn
+            | PasteError::LoggerInit(_) => PbcliStatus::Other,
+        }
+    }
+}
+
+/// Format of a new paste, passed to `pbcli_paste_options_set_format` as an integer
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PbcliFormat {
+    Plaintext = 0,
+    Syntax = 1,
+    Markdown = 2,
+    /// Detect the format from the paste text
+    Auto = 3,
+}
+
+impl PbcliFormat {
+    fn from_raw(value: u32) -> Option<Self> {
+        [Self::Plaintext, Self::Syntax, Self::Markdown, Self::Auto]
+            .into_iter()
+            .find(|format| *format as u32 == value)
+    }
+}
+
+/// Connection to a PrivateBin instance
+pub struct PbcliClient {
+    base: Url,
+    config: ClientConfig,
+}
+
+impl PbcliClient {
+    fn api(&self) -> API {
+        API::new(self.base.clone(), self.config.clone())
+    }
+}
+
+/// Settings of a new paste
+pub struct PbcliPasteOptions(PasteOptions);
+
+/// A posted paste
+pub struct PbcliPostResult {
+    paste_id: CString,
+    key: SecretCString,
+    paste_url: SecretCString,
+    delete_url: SecretCString,
+    delete_token: SecretCString,
+}
+
+/// A fetched, still encrypted paste with its comments
+pub struct PbcliPaste(Paste);
+
+/// Decrypted text and attachment of a paste
+pub struct PbcliDecryptedPaste {
+    text: SecretCString,
+    attachment: Option<SecretCString>,
+    attachment_name: Option<SecretCString>,
+}
+
+/// Comments of a paste, in thread order
+pub struct PbcliComments(Vec<CommentEntry>);
+
+struct CommentEntry {
+    id: CString,
+    parent_id: CString,
+    nickname: Option<SecretCString>,
+    text: Option<SecretCString>,
+    error: Option<CString>,
+    created: i64,
+    depth: u32,
+}
+
+/// A comment borrowed from `PbcliComments`, valid until the comments are freed
+#[repr(C)]
+pub struct PbcliComment {
+    pub id: *const c_char,
+    /// Id of the comment this one replies to, or the paste id
+    pub parent_id: *const c_char,
+    /// NULL if the comment has no nickname or could not be decrypted
+    pub nickname: *const c_char,
+    /// NULL if the comment could not be decrypted
+    pub text: *const c_char,
+    /// Why the comment could not be decrypted, NULL if it was decrypted
+    pub error: *const c_char,
+    /// Unix time the comment was created, 0 if unknown
+    pub created: i64,
+    /// Nesting level in its thread, 0 for comments on the paste itself
+    pub depth: u32,
+}
+
+/// C string which is overwritten when dropped
+struct SecretCString(CString);
+
+impl SecretCString {
+    fn as_ptr(&self) -> *const c_char {
+        self.0.as_ptr()
+    }
+}
+
+impl Drop for SecretCString {
+    fn drop(&mut self) {
+        std::mem::take(&mut self.0).into_bytes_with_nul().zeroize();
+    }
+}
+
+#[derive(Debug)]
+struct CError {
+    status: PbcliStatus,
+    message: String,
+}
+
+impl From<PasteError> for CError {
+    fn from(err: PasteError) -> Self {
+        Self {
+            status: PbcliStatus::from(&err),
+            message: err.to_string(),
+        }
+    }
+}
+
+fn invalid_argument(message: impl Into<String>) -> CError {
+    CError {
+        status: PbcliStatus::InvalidArgument,
+        message: message.into(),
+    }
+}
+
+type CResult<T> = Result<T, CError>;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run the body of an exported function, turning errors and panics into a status
+fn ffi_call(body: impl FnOnce() -> CResult<()>) -> PbcliStatus {
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => PbcliStatus::Ok,
+        Ok(Err(err)) => {
+            set_last_error(err.message);
+            err.status
+        }
+        Err(_) => {
+            set_last_error("pbcli panicked".into());
+            PbcliStatus::Panic
+        }
+    }
+}
+
+unsafe fn str_arg<'a>(value: *const c_char, name: &str) -> CResult<&'a str> {
+    if value.is_null() {
+        return Err(invalid_argument(format!("{name} is NULL")));
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|_| invalid_argument(format!("{name} is not valid UTF-8")))
+}
+
+unsafe fn optional_str_arg<'a>(value: *const c_char, name: &str) -> CResult<Option<&'a str>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    str_arg(value, name).map(Some)
+}
+
+unsafe fn handle_arg<'a, T>(handle: *const T, name: &str) -> CResult<&'a T> {
+    handle
+        .as_ref()
+        .ok_or_else(|| invalid_argument(format!("{name} is NULL")))
+}
+
+unsafe fn handle_arg_mut<'a, T>(handle: *mut T, name: &str) -> CResult<&'a mut T> {
+    handle
+        .as_mut()
+        .ok_or_else(|| invalid_argument(format!("{name} is NULL")))
+}
+
+fn c_string(value: impl Into<Vec<u8>>) -> CResult<CString> {
+    CString::new(value).map_err(|_| CError {
+        status: PbcliStatus::Other,
+        message: "text contains a NUL byte, which C strings can not hold".into(),
+    })
+}
+
+fn secret_c_string(value: &str) -> CResult<SecretCString> {
+    c_string(value).map(SecretCString)
+}
+
+unsafe fn write_out<T>(out: *mut T, value: T) {
+    out.write(value);
+}
+
+fn check_out<T>(out: *mut T, name: &str) -> CResult<()> {
+    if out.is_null() {
+        return Err(invalid_argument(format!("{name} is NULL")));
+    }
+    Ok(())
+}
+
+unsafe fn free_handle<T>(handle: *mut T) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Version of the library, a static string
+#[no_mangle]
+pub extern "C" fn pbcli_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+/// Message of the last failed call on this thread, NULL if there was none.
+/// The string stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn pbcli_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Free a string returned by pbcli. Passing NULL is allowed.
+///
+/// # Safety
+/// `value` must be NULL or a string returned by pbcli which has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(SecretCString(CString::from_raw(value)));
+    }
+}
+
+/// Create a client for the instance at `url`
+///
+/// # Safety
+/// `url` must be a NUL terminated string and `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_client_new(
+    url: *const c_char,
+    out: *mut *mut PbcliClient,
+) -> PbcliStatus {
+    ffi_call(|| {
+        check_out(out, "out")?;
+        let url = str_arg(url, "url")?;
+        let mut base = Url::parse(url).map_err(PasteError::from)?;
+        base.set_fragment(None);
+        base.set_query(None);
+        let client = PbcliClient {
+            base,
+            config: ClientConfig::default(),
+        };
+        write_out(out, Box::into_raw(Box::new(client)));
+        Ok(())
+    })
+}
+
+/// Set the connection timeout, requests may take four times as long
+///
+/// # Safety
+/// `client` must be a client returned by `pbcli_client_new`.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_client_set_timeout(
+    client: *mut PbcliClient,
+    seconds: u64,
+) -> PbcliStatus {
+    ffi_call(|| {
+        let client = handle_arg_mut(client, "client")?;
+        client.config.timeout = Some(Duration::from_secs(seconds));
+        Ok(())
+    })
+}
+
+/// Accept invalid TLS certificates
+///
+/// # Safety
+/// `client` must be a client returned by `pbcli_client_new`.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_client_set_insecure(
+    client: *mut PbcliClient,
+    insecure: bool,
+) -> PbcliStatus {
+    ffi_call(|| {
+        let client = handle_arg_mut(client, "client")?;
+        client.config.insecure = insecure;
+        Ok(())
+    })
+}
+
+/// Trust the certificates in a PEM bundle in addition to the system ones
+///
+/// # Safety
+/// `client` must be a client returned by `pbcli_client_new` and `path` a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_client_set_ca_cert(
+    client: *mut PbcliClient,
+    path: *const c_char,
+) -> PbcliStatus {
+    ffi_call(|| {
+        let client = handle_arg_mut(client, "client")?;
+        let path = str_arg(path, "path")?;
+        client.config.ca_cert = Some(path.into());
+        Ok(())
+    })
+}
+
+/// Authenticate with an access token obtained from an oidc token endpoint
+///
+/// # Safety
+/// `client` must be a client returned by `pbcli_client_new`, the other arguments
+/// NUL terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_client_set_oidc(
+    client: *mut PbcliClient,
+    token_url: *const c_char,
+    client_id: *const c_char,
+    username: *const c_char,
+    password: *const c_char,
+) -> PbcliStatus {
+    ffi_call(|| {
+        let client = handle_arg_mut(client, "client")?;
+        client.config.oidc = Some(OidcAuth {
+            token_url: str_arg(token_url, "token_url")?.to_string(),
+            client_id: str_arg(client_id, "client_id")?.to_string(),
+            username: str_arg(username, "username")?.to_string(),
+            password: SecretString::from(str_arg(password, "password")?),
+        });
+        Ok(())
+    })
+}
+
+/// Free a client. Passing NULL is allowed.
+///
+/// # Safety
+/// `client` must be NULL or a client returned by `pbcli_client_new` which has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_client_free(client: *mut PbcliClient) {
+    free_handle(client);
+}
+
+/// Default settings for a new paste: plaintext, expiring after a week,
+/// without burn after reading and discussion
+#[no_mangle]
+pub extern "C" fn pbcli_paste_options_new() -> *mut PbcliPasteOptions {
+    Box::into_raw(Box::new(PbcliPasteOptions(PasteOptions::default())))
+}
+
+/// Set the expiry, one of the values offered by the instance such as "1day"
+///
+/// # Safety
+/// `options` must be returned by `pbcli_paste_options_new` and `expire` a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_paste_options_set_expire(
+    options: *mut PbcliPasteOptions,
+    expire: *const c_char,
+) -> PbcliStatus {
+    ffi_call(|| {
+        let options = handle_arg_mut(options, "options")?;
+        options.0.expire = str_arg(expire, "expire")?.to_string();
+        Ok(())
+    })
+}
+
+/// Set the format to one of the `PbcliFormat` values
+///
+/// # Safety
+/// `options` must be returned by `pbcli_paste_options_new`.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_paste_options_set_format(
+    options: *mut PbcliPasteOptions,
+    format: u32,
+) -> PbcliStatus {
+    ffi_call(|| {
+        let options = handle_arg_mut(options, "options")?;
+        let format = PbcliFormat::from_raw(format)
+            .ok_or_else(|| invalid_argument(format!("{format} is not a PbcliFormat")))?;
+        options.0.auto_format = format == PbcliFormat::Auto;
+        options.0.format = match format {
+            PbcliFormat::Plaintext | PbcliFormat::Auto => PasteFormat::Plaintext,
+            PbcliFormat::Syntax => PasteFormat::Syntax,
+            PbcliFormat::Markdown => PasteFormat::Markdown,
+        };
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `options` must be returned by `pbcli_paste_options_new`.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_paste_options_set_burn(
+    options: *mut PbcliPasteOptions,
+    burn: bool,
+) -> PbcliStatus {
+    ffi_call(|| {
+        let options = handle_arg_mut(options, "options")?;
+        options.0.burn = burn;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `options` must be returned by `pbcli_paste_options_new`.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_paste_options_set_discussion(
+    options: *mut PbcliPasteOptions,
+    discussion: bool,
+) -> PbcliStatus {
+    ffi_call(|| {
+        let options = handle_arg_mut(options, "options")?;
+        options.0.discussion = discussion;
+        Ok(())
+    })
+}
+
+/// Fail with `PBCLI_STATUS_SIZE_LIMIT` if the encrypted paste is larger, 0 removes the limit
+///
+/// # Safety
+/// `options` must be returned by `pbcli_paste_options_new`.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_paste_options_set_size_limit(
+    options: *mut PbcliPasteOptions,
+    bytes: u64,
+) -> PbcliStatus {
+    ffi_call(|| {
+        let options = handle_arg_mut(options, "options")?;
+        options.0.size_limit = (bytes > 0).then_some(bytes);
+        Ok(())
+    })
+}
+
+/// Free paste options. Passing NULL is allowed.
+///
+/// # Safety
+/// `options` must be NULL or returned by `pbcli_paste_options_new` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_paste_options_free(options: *mut PbcliPasteOptions) {
+    free_handle(options);
+}
+
+/// Encrypt and post a paste
+///
+/// `password` and `options` may be NULL for no password and default options.
+///
+/// # Safety
+/// `client` must be a client returned by `pbcli_client_new`, `text` and `password`
+/// NUL terminated strings, `options` returned by `pbcli_paste_options_new` and `out`
+/// must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_post_paste(
+    client: *const PbcliClient,
+    text: *const c_char,
+    password: *const c_char,
+    options: *const PbcliPasteOptions,
+    out: *mut *mut PbcliPostResult,
+) -> PbcliStatus {
+    ffi_call(|| {
+        check_out(out, "out")?;
+        let client = handle_arg(client, "client")?;
+        let text = str_arg(text, "text")?;
+        let password = optional_str_arg(password, "password")?.unwrap_or_default();
+        let default_options = PasteOptions::default();
+        let options = options
+            .as_ref()
+            .map_or(&default_options, |options| &options.0);
+
+        let mut content = DecryptedPaste {
+            paste: text.to_string(),
+            attachment: None,
+            attachment_name: None,
+        };
+        let response = client.api().post_paste(&content, password, options);
+        content.zeroize();
+        let result = post_result(&response?)?;
+        write_out(out, Box::into_raw(Box::new(result)));
+        Ok(())
+    })
+}
+
+fn post_result(response: &PostPasteResponse) -> CResult<PbcliPostResult> {
+    Ok(PbcliPostResult {
+        paste_id: c_string(response.id.as_str())?,
+        key: secret_c_string(&response.bs58key)?,
+        paste_url: secret_c_string(response.to_paste_url().as_str())?,
+        delete_url: secret_c_string(response.to_delete_url().as_str())?,
+        delete_token: secret_c_string(&response.deletetoken)?,
+    })
+}
+
+/// Id of the posted paste, valid until the result is freed
+///
+/// # Safety
+/// `result` must be returned by `pbcli_post_paste` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_post_result_paste_id(
+    result: *const PbcliPostResult,
+) -> *const c_char {
+    result
+        .as_ref()
+        .map_or(ptr::null(), |result| result.paste_id.as_ptr())
+}
+
+/// Key to decrypt the posted paste, valid until the result is freed
+///
+/// # Safety
+/// `result` must be returned by `pbcli_post_paste` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_post_result_key(result: *const PbcliPostResult) -> *const c_char {
+    result
+        .as_ref()
+        .map_or(ptr::null(), |result| result.key.as_ptr())
+}
+
+/// Url of the posted paste including its key, valid until the result is freed
+///
+/// # Safety
+/// `result` must be returned by `pbcli_post_paste` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_post_result_paste_url(
+    result: *const PbcliPostResult,
+) -> *const c_char {
+    result
+        .as_ref()
+        .map_or(ptr::null(), |result| result.paste_url.as_ptr())
+}
+
+/// Url which deletes the posted paste, valid until the result is freed
+///
+/// # Safety
+/// `result` must be returned by `pbcli_post_paste` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_post_result_delete_url(
+    result: *const PbcliPostResult,
+) -> *const c_char {
+    result
+        .as_ref()
+        .map_or(ptr::null(), |result| result.delete_url.as_ptr())
+}
+
+/// Token for `pbcli_delete_paste`, valid until the result is freed
+///
+/// # Safety
+/// `result` must be returned by `pbcli_post_paste` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_post_result_delete_token(
+    result: *const PbcliPostResult,
+) -> *const c_char {
+    result
+        .as_ref()
+        .map_or(ptr::null(), |result| result.delete_token.as_ptr())
+}
+
+/// Free a post result. Passing NULL is allowed.
+///
+/// # Safety
+/// `result` must be NULL or returned by `pbcli_post_paste` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_post_result_free(result: *mut PbcliPostResult) {
+    free_handle(result);
+}
+
+/// Fetch a paste with its comments. Burn after reading pastes are deleted by fetching them.
+///
+/// # Safety
+/// `client` must be a client returned by `pbcli_client_new`, `paste_id` a NUL terminated
+/// string and `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_get_paste(
+    client: *const PbcliClient,
+    paste_id: *const c_char,
+    out: *mut *mut PbcliPaste,
+) -> PbcliStatus {
+    ffi_call(|| {
+        check_out(out, "out")?;
+        let client = handle_arg(client, "client")?;
+        let paste_id = str_arg(paste_id, "paste_id")?;
+        let paste = client.api().get_paste(paste_id)?;
+        write_out(out, Box::into_raw(Box::new(PbcliPaste(paste))));
+        Ok(())
+    })
+}
+
+/// Decrypt a fetched paste. `password` may be NULL if the paste has none.
+///
+/// # Safety
+/// `paste` must be returned by `pbcli_get_paste`, `key` and `password` NUL terminated
+/// strings and `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_paste_decrypt(
+    paste: *const PbcliPaste,
+    key: *const c_char,
+    password: *const c_char,
+    out: *mut *mut PbcliDecryptedPaste,
+) -> PbcliStatus {
+    ffi_call(|| {
+        check_out(out, "out")?;
+        let paste = handle_arg(paste, "paste")?;
+        let key = str_arg(key, "key")?;
+        let password = optional_str_arg(password, "password")?.unwrap_or_default();
+        let mut content =
+            paste
+                .0
+                .decrypt_with_limits(key, password, DecryptionLimits::default())?;
+        let decrypted = decrypted_paste(&content);
+        content.zeroize();
+        write_out(out, Box::into_raw(Box::new(decrypted?)));
+        Ok(())
+    })
+}
+
+fn decrypted_paste(content: &DecryptedPaste) -> CResult<PbcliDecryptedPaste> {
+    Ok(PbcliDecryptedPaste {
+        text: secret_c_string(&content.paste)?,
+        attachment: content
+            .attachment
+            .as_deref()
+            .map(secret_c_string)
+            .transpose()?,
+        attachment_name: content
+            .attachment_name
+            .as_deref()
+            .map(secret_c_string)
+            .transpose()?,
+    })
+}
+
+/// Text of the paste, valid until the decrypted paste is freed
+///
+/// # Safety
+/// `paste` must be returned by `pbcli_paste_decrypt` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_decrypted_paste_text(
+    paste: *const PbcliDecryptedPaste,
+) -> *const c_char {
+    paste
+        .as_ref()
+        .map_or(ptr::null(), |paste| paste.text.as_ptr())
+}
+
+/// Attachment as data url, NULL if the paste has none
+///
+/// # Safety
+/// `paste` must be returned by `pbcli_paste_decrypt` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_decrypted_paste_attachment(
+    paste: *const PbcliDecryptedPaste,
+) -> *const c_char {
+    paste
+        .as_ref()
+        .and_then(|paste| paste.attachment.as_ref())
+        .map_or(ptr::null(), |attachment| attachment.as_ptr())
+}
+
+/// File name of the attachment, NULL if the paste has none
+///
+/// # Safety
+/// `paste` must be returned by `pbcli_paste_decrypt` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_decrypted_paste_attachment_name(
+    paste: *const PbcliDecryptedPaste,
+) -> *const c_char {
+    paste
+        .as_ref()
+        .and_then(|paste| paste.attachment_name.as_ref())
+        .map_or(ptr::null(), |name| name.as_ptr())
+}
+
+/// Free a decrypted paste, its text is overwritten. Passing NULL is allowed.
+///
+/// # Safety
+/// `paste` must be NULL or returned by `pbcli_paste_decrypt` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_decrypted_paste_free(paste: *mut PbcliDecryptedPaste) {
+    free_handle(paste);
+}
+
+/// Decrypt the comments of a fetched paste. A comment which fails to decrypt does not fail
+/// the call, its `error` is set instead. `password` may be NULL if the paste has none.
+///
+/// # Safety
+/// `paste` must be returned by `pbcli_get_paste`, `key` and `password` NUL terminated
+/// strings and `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_paste_decrypt_comments(
+    paste: *const PbcliPaste,
+    key: *const c_char,
+    password: *const c_char,
+    out: *mut *mut PbcliComments,
+) -> PbcliStatus {
+    ffi_call(|| {
+        check_out(out, "out")?;
+        let paste = handle_arg(paste, "paste")?;
+        let key = str_arg(key, "key")?;
+        let password = optional_str_arg(password, "password")?.unwrap_or_default();
+        let mut tree = paste
+            .0
+            .comment_tree_with_limits(key, password, DecryptionLimits::default());
+        let mut entries = vec![];
+        let flattened = flatten_comments(&tree, 0, &mut entries);
+        tree.zeroize();
+        flattened?;
+        write_out(out, Box::into_raw(Box::new(PbcliComments(entries))));
+        Ok(())
+    })
+}
+
+fn flatten_comments(
+    nodes: &[CommentNode],
+    depth: u32,
+    entries: &mut Vec<CommentEntry>,
+) -> CResult<()> {
+    for node in nodes {
+        entries.push(CommentEntry {
+            id: c_string(node.id.as_str())?,
+            parent_id: c_string(node.parent_id.as_str())?,
+            nickname: node.nickname.as_deref().map(secret_c_string).transpose()?,
+            text: node.comment.as_deref().map(secret_c_string).transpose()?,
+            error: node.decrypt_error.as_deref().map(c_string).transpose()?,
+            created: node.created.map(i64::from).unwrap_or_default(),
+            depth,
+        });
+        flatten_comments(&node.replies, depth + 1, entries)?;
+    }
+    Ok(())
+}
+
+/// Number of comments, 0 if `comments` is NULL
+///
+/// # Safety
+/// `comments` must be NULL or returned by `pbcli_paste_decrypt_comments` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_comments_count(comments: *const PbcliComments) -> usize {
+    comments.as_ref().map_or(0, |comments| comments.0.len())
+}
+
+/// Comment at `index`. Replies follow the comment they reply to.
+///
+/// # Safety
+/// `comments` must be returned by `pbcli_paste_decrypt_comments` and not freed yet,
+/// `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_comments_get(
+    comments: *const PbcliComments,
+    index: usize,
+    out: *mut PbcliComment,
+) -> PbcliStatus {
+    ffi_call(|| {
+        check_out(out, "out")?;
+        let comments = handle_arg(comments, "comments")?;
+        let entry = comments.0.get(index).ok_or_else(|| {
+            invalid_argument(format!(
+                "index {index} is out of range, there are {} comments",
+                comments.0.len()
+            ))
+        })?;
+        let optional = |value: &Option<SecretCString>| {
+            value.as_ref().map_or(ptr::null(), |value| value.as_ptr())
+        };
+        write_out(
+            out,
+            PbcliComment {
+                id: entry.id.as_ptr(),
+                parent_id: entry.parent_id.as_ptr(),
+                nickname: optional(&entry.nickname),
+                text: optional(&entry.text),
+                error: entry
+                    .error
+                    .as_ref()
+                    .map_or(ptr::null(), |error| error.as_ptr()),
+                created: entry.created,
+                depth: entry.depth,
+            },
+        );
+        Ok(())
+    })
+}
+
+/// Free comments, their text is overwritten. Passing NULL is allowed.
+///
+/// # Safety
+/// `comments` must be NULL or returned by `pbcli_paste_decrypt_comments` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_comments_free(comments: *mut PbcliComments) {
+    free_handle(comments);
+}
+
+/// Free a fetched paste. Passing NULL is allowed.
+///
+/// # Safety
+/// `paste` must be NULL or returned by `pbcli_get_paste` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_paste_free(paste: *mut PbcliPaste) {
+    free_handle(paste);
+}
+
+/// Encrypt and post a comment on a paste
+///
+/// `parent_id` may be NULL to comment on the paste itself, `password` if the paste has none
+/// and `nickname` to post anonymously. `comment_id` may be NULL if the id of the new comment
+/// is not needed, otherwise it receives a string to free with `pbcli_string_free`.
+///
+/// # Safety
+/// `client` must be a client returned by `pbcli_client_new`, the strings NUL terminated
+/// and `comment_id` NULL or pointing to writable memory.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn pbcli_post_comment(
+    client: *const PbcliClient,
+    paste_id: *const c_char,
+    key: *const c_char,
+    password: *const c_char,
+    parent_id: *const c_char,
+    nickname: *const c_char,
+    text: *const c_char,
+    comment_id: *mut *mut c_char,
+) -> PbcliStatus {
+    ffi_call(|| {
+        let client = handle_arg(client, "client")?;
+        let paste_id = str_arg(paste_id, "paste_id")?;
+        let key = str_arg(key, "key")?;
+        let password = optional_str_arg(password, "password")?.unwrap_or_default();
+        let parent_id = optional_str_arg(parent_id, "parent_id")?.unwrap_or(paste_id);
+        let nickname = optional_str_arg(nickname, "nickname")?;
+        let text = str_arg(text, "text")?;
+
+        let mut content = DecryptedComment {
+            comment: text.to_string(),
+            nickname: nickname.map(|nickname| nickname.to_string()),
+        };
+        let response = client.api().post_comment(
+            &content,
+            paste_id,
+            parent_id,
+            key,
+            password,
+            &CommentOptions::default(),
+        );
+        content.zeroize();
+        let response = response?;
+        if !comment_id.is_null() {
+            write_out(comment_id, c_string(response.id)?.into_raw());
+        }
+        Ok(())
+    })
+}
+
+/// Delete a paste with the token returned when it was posted
+///
+/// # Safety
+/// `client` must be a client returned by `pbcli_client_new`, `paste_id` and
+/// `delete_token` NUL terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn pbcli_delete_paste(
+    client: *const PbcliClient,
+    paste_id: *const c_char,
+    delete_token: *const c_char,
+) -> PbcliStatus {
+    ffi_call(|| {
+        let client = handle_arg(client, "client")?;
+        let paste_id = str_arg(paste_id, "paste_id")?;
+        let delete_token = str_arg(delete_token, "delete_token")?;
+        client.api().delete_paste(paste_id, delete_token)?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        let message = pbcli_last_error_message();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    fn new_client() -> *mut PbcliClient {
+        let mut client = ptr::null_mut();
+        let status = unsafe { pbcli_client_new(c"https://privatebin.net/".as_ptr(), &mut client) };
+        assert_eq!(status, PbcliStatus::Ok);
+        client
+    }
+
+    #[test]
+    fn null_arguments_are_rejected() {
+        let mut client = ptr::null_mut();
+        let status = unsafe { pbcli_client_new(ptr::null(), &mut client) };
+        assert_eq!(status, PbcliStatus::InvalidArgument);
+        assert_eq!(last_error(), "url is NULL");
+        assert!(client.is_null());
+
+        let status =
+            unsafe { pbcli_client_new(c"https://privatebin.net/".as_ptr(), ptr::null_mut()) };
+        assert_eq!(status, PbcliStatus::InvalidArgument);
+        assert_eq!(last_error(), "out is NULL");
+
+        let client = new_client();
+        let mut paste = ptr::null_mut();
+        let status = unsafe { pbcli_get_paste(client, ptr::null(), &mut paste) };
+        assert_eq!(status, PbcliStatus::InvalidArgument);
+        assert_eq!(last_error(), "paste_id is NULL");
+
+        let status = unsafe { pbcli_get_paste(ptr::null(), c"id".as_ptr(), &mut paste) };
+        assert_eq!(status, PbcliStatus::InvalidArgument);
+        assert_eq!(last_error(), "client is NULL");
+        assert!(paste.is_null());
+
+        let status = unsafe { pbcli_delete_paste(client, c"id".as_ptr(), ptr::null()) };
+        assert_eq!(status, PbcliStatus::InvalidArgument);
+        assert_eq!(last_error(), "delete_token is NULL");
+
+        let status = unsafe { pbcli_paste_options_set_burn(ptr::null_mut(), true) };
+        assert_eq!(status, PbcliStatus::InvalidArgument);
+        assert_eq!(last_error(), "options is NULL");
+
+        unsafe { pbcli_client_free(client) };
+    }
+
+    #[test]
+    fn invalid_utf8_is_rejected() {
+        let mut client = ptr::null_mut();
+        let url = c"https://privatebin.net/\xff";
+        let status = unsafe { pbcli_client_new(url.as_ptr(), &mut client) };
+        assert_eq!(status, PbcliStatus::InvalidArgument);
+        assert_eq!(last_error(), "url is not valid UTF-8");
+    }
+
+    #[test]
+    fn unknown_formats_are_rejected() {
+        let options = pbcli_paste_options_new();
+        let status = unsafe { pbcli_paste_options_set_format(options, 42) };
+        assert_eq!(status, PbcliStatus::InvalidArgument);
+        assert_eq!(last_error(), "42 is not a PbcliFormat");
+
+        let status =
+            unsafe { pbcli_paste_options_set_format(options, PbcliFormat::Markdown as u32) };
+        assert_eq!(status, PbcliStatus::Ok);
+        assert_eq!(unsafe { &*options }.0.format, PasteFormat::Markdown);
+        assert!(!unsafe { &*options }.0.auto_format);
+
+        let status = unsafe { pbcli_paste_options_set_format(options, PbcliFormat::Auto as u32) };
+        assert_eq!(status, PbcliStatus::Ok);
+        assert!(unsafe { &*options }.0.auto_format);
+        unsafe { pbcli_paste_options_free(options) };
+    }
+
+    #[test]
+    fn free_accepts_null() {
+        unsafe {
+            pbcli_string_free(ptr::null_mut());
+            pbcli_client_free(ptr::null_mut());
+            pbcli_paste_options_free(ptr::null_mut());
+            pbcli_post_result_free(ptr::null_mut());
+            pbcli_decrypted_paste_free(ptr::null_mut());
+            pbcli_comments_free(ptr::null_mut());
+            pbcli_paste_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn comments_get_checks_the_index() {
+        let tree = vec![CommentNode::sample("a", "paste", vec![])];
+        let mut entries = vec![];
+        flatten_comments(&tree, 0, &mut entries).unwrap();
+        let comments = Box::into_raw(Box::new(PbcliComments(entries)));
+        assert_eq!(unsafe { pbcli_comments_count(comments) }, 1);
+        assert_eq!(unsafe { pbcli_comments_count(ptr::null()) }, 0);
+
+        let mut comment = std::mem::MaybeUninit::<PbcliComment>::uninit();
+        let status = unsafe { pbcli_comments_get(comments, 0, comment.as_mut_ptr()) };
+        assert_eq!(status, PbcliStatus::Ok);
+        let comment = unsafe { comment.assume_init() };
+        assert_eq!(unsafe { CStr::from_ptr(comment.id) }, c"a");
+        assert_eq!(unsafe { CStr::from_ptr(comment.text) }, c"text of a");
+        assert!(comment.nickname.is_null());
+        assert!(comment.error.is_null());
+
+        let mut comment = std::mem::MaybeUninit::<PbcliComment>::uninit();
+        let status = unsafe { pbcli_comments_get(comments, 1, comment.as_mut_ptr()) };
+        assert_eq!(status, PbcliStatus::InvalidArgument);
+        assert_eq!(
+            last_error(),
+            "index 1 is out of range, there are 1 comments"
+        );
+
+        let status = unsafe { pbcli_comments_get(comments, 0, ptr::null_mut()) };
+        assert_eq!(status, PbcliStatus::InvalidArgument);
+        unsafe { pbcli_comments_free(comments) };
+    }
+
+    #[test]
+    fn flatten_comments_keeps_thread_order() {
+        let tree = vec![
+            CommentNode::sample(
+                "a",
+                "paste",
+                vec![
+                    CommentNode::sample("b", "a", vec![CommentNode::sample("c", "b", vec![])]),
+                    CommentNode::sample("d", "a", vec![]),
+                ],
+            ),
+            CommentNode::sample("e", "paste", vec![]),
+        ];
+        let mut entries = vec![];
+        flatten_comments(&tree, 0, &mut entries).unwrap();
+        let flattened: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.id.to_str().unwrap(), entry.depth))
+            .collect();
+        assert_eq!(
+            flattened,
+            [("a", 0), ("b", 1), ("c", 2), ("d", 1), ("e", 0)]
+        );
+        assert_eq!(entries[2].parent_id.as_c_str(), c"b");
+    }
+
+    #[test]
+    fn status_of_errors() {
+        assert_eq!(
+            PbcliStatus::from(&PasteError::PasteNotFound(Some(
+                "Paste does not exist, has expired or has been deleted.".into()
+            ))),
+            PbcliStatus::NotFound
+        );
+        assert_eq!(
+            PbcliStatus::from(&PasteError::ServerError {
+                http_status: 200,
+                message: "Please wait 10 seconds between each post.".into(),
+            }),
+            PbcliStatus::Server
+        );
+        assert_eq!(
+            PbcliStatus::from(&PasteError::UnexpectedResponse {
+                http_status: 502,
+                excerpt: "<html>".into(),
+            }),
+            PbcliStatus::Server
+        );
+        assert_eq!(
+            PbcliStatus::from(&PasteError::KdfIterationsExceeded {
+                iterations: 10,
+                limit: 1,
+            }),
+            PbcliStatus::Decryption
+        );
+        assert_eq!(
+            PbcliStatus::from(&PasteError::MissingOption("oidc_username".into())),
+            PbcliStatus::Auth
+        );
+        assert_eq!(PbcliStatus::from(&PasteError::FileExists), PbcliStatus::Io);
+        assert_eq!(
+            PbcliStatus::from(&PasteError::BurnAfterReading),
+            PbcliStatus::Other
+        );
+    }
+}
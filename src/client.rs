@@ -0,0 +1,256 @@
+use crate::cache::DEFAULT_CAPABILITIES_TTL;
+use crate::error::PbResult;
+use crate::privatebin::{
+    Cipher, CompressionType, DecryptedPaste, PasteFormat, DEFAULT_COMPRESSION_LEVEL,
+    DEFAULT_KDF_ITERATIONS,
+};
+use crate::secret::SecretString;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Credentials to obtain an access token from an oidc token endpoint with the password grant
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct OidcAuth {
+    pub token_url: String,
+    pub client_id: String,
+    pub username: String,
+    pub password: SecretString,
+}
+
+/// Connection settings of an `API` client, usually created through `ClientConfig::builder`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct ClientConfig {
+    /// Connection timeout, requests may take four times as long
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    pub timeout: Option<Duration>,
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    pub insecure: bool,
+    /// PEM bundle of additional root certificates
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    pub ca_cert: Option<PathBuf>,
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    pub oidc: Option<OidcAuth>,
+    /// Read and write the capability cache
+    #[cfg_attr(feature = "uniffi", uniffi(default = true))]
+    pub capabilities_cache: bool,
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    pub capabilities_ttl: Option<Duration>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            insecure: false,
+            ca_cert: None,
+            oidc: None,
+            capabilities_cache: true,
+            capabilities_ttl: None,
+        }
+    }
+}
+
+impl ClientConfig {
+    pub fn builder() -> ClientConfigBuilder {
+        ClientConfigBuilder::default()
+    }
+
+    pub fn timeout_or_default(&self) -> Duration {
+        self.timeout.unwrap_or(DEFAULT_TIMEOUT)
+    }
+
+    /// Seconds cached capabilities stay fresh
+    pub fn capabilities_ttl_secs(&self) -> u64 {
+        self.capabilities_ttl
+            .map(|ttl| ttl.as_secs())
+            .unwrap_or(DEFAULT_CAPABILITIES_TTL)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ClientConfigBuilder {
+    config: ClientConfig,
+}
+
+impl ClientConfigBuilder {
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Accept invalid TLS certificates
+    pub fn insecure(mut self, insecure: bool) -> Self {
+        self.config.insecure = insecure;
+        self
+    }
+
+    pub fn ca_cert(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.ca_cert = Some(path.into());
+        self
+    }
+
+    pub fn oidc(mut self, auth: OidcAuth) -> Self {
+        self.config.oidc = Some(auth);
+        self
+    }
+
+    pub fn capabilities_cache(mut self, enabled: bool) -> Self {
+        self.config.capabilities_cache = enabled;
+        self
+    }
+
+    pub fn capabilities_ttl(mut self, ttl: Duration) -> Self {
+        self.config.capabilities_ttl = Some(ttl);
+        self
+    }
+
+    pub fn build(self) -> ClientConfig {
+        self.config
+    }
+}
+
+/// Settings of a new paste
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct PasteOptions {
+    #[cfg_attr(feature = "uniffi", uniffi(default = "1week"))]
+    pub expire: String,
+    pub format: PasteFormat,
+    /// Detect the format from `file_name` or the text instead of using `format`
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    pub auto_format: bool,
+    /// Name of the file the text came from, used by `auto_format`
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    pub file_name: Option<String>,
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    pub burn: bool,
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    pub discussion: bool,
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    pub size_limit: Option<u64>,
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    pub kdf_iterations: Option<u32>,
+    pub compression: CompressionType,
+    #[cfg_attr(feature = "uniffi", uniffi(default = 10))]
+    pub compression_level: u8,
+}
+
+impl Default for PasteOptions {
+    fn default() -> Self {
+        Self {
+            expire: "1week".into(),
+            format: PasteFormat::Plaintext,
+            auto_format: false,
+            file_name: None,
+            burn: false,
+            discussion: false,
+            size_limit: None,
+            kdf_iterations: None,
+            compression: CompressionType::Zlib,
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+}
+
+impl PasteOptions {
+    /// Format for the paste, detected from its text with `auto_format`.
+    /// The attachment name says nothing about the text and is not used.
+    pub fn paste_format(&self, content: &DecryptedPaste) -> PasteFormat {
+        if !self.auto_format {
+            return self.format;
+        }
+        PasteFormat::detect(&content.paste, self.file_name.as_deref())
+    }
+
+    pub fn cipher(&self) -> PbResult<Cipher> {
+        Cipher::with_settings(
+            self.kdf_iterations.unwrap_or(DEFAULT_KDF_ITERATIONS),
+            self.compression,
+        )
+    }
+}
+
+/// Settings of a new comment
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct CommentOptions {
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    pub size_limit: Option<u64>,
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    pub kdf_iterations: Option<u32>,
+    pub compression: CompressionType,
+    #[cfg_attr(feature = "uniffi", uniffi(default = 10))]
+    pub compression_level: u8,
+}
+
+impl Default for CommentOptions {
+    fn default() -> Self {
+        Self {
+            size_limit: None,
+            kdf_iterations: None,
+            compression: CompressionType::Zlib,
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+}
+
+impl CommentOptions {
+    pub fn cipher(&self) -> PbResult<Cipher> {
+        Cipher::with_settings(
+            self.kdf_iterations.unwrap_or(DEFAULT_KDF_ITERATIONS),
+            self.compression,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paste_with_attachment(text: &str, attachment_name: &str) -> DecryptedPaste {
+        DecryptedPaste {
+            paste: text.into(),
+            attachment: Some("data:text/plain;base64,".into()),
+            attachment_name: Some(attachment_name.into()),
+        }
+    }
+
+    #[test]
+    fn paste_format_ignores_attachment_name() {
+        let options = PasteOptions {
+            auto_format: true,
+            ..Default::default()
+        };
+        let content = paste_with_attachment("# Notes\n\n- see [main.rs](main.rs)\n", "main.rs");
+        assert_eq!(options.paste_format(&content), PasteFormat::Markdown);
+
+        let content = paste_with_attachment("see the attached file", "main.rs");
+        assert_eq!(options.paste_format(&content), PasteFormat::Plaintext);
+    }
+
+    #[test]
+    fn paste_format_uses_text_file_name() {
+        let options = PasteOptions {
+            auto_format: true,
+            file_name: Some("main.rs".into()),
+            ..Default::default()
+        };
+        let content = paste_with_attachment("see the attached file", "notes.md");
+        assert_eq!(options.paste_format(&content), PasteFormat::Syntax);
+    }
+
+    #[test]
+    fn paste_format_without_detection() {
+        let options = PasteOptions {
+            format: PasteFormat::Markdown,
+            file_name: Some("main.rs".into()),
+            ..Default::default()
+        };
+        let content = paste_with_attachment("fn main() {}", "main.rs");
+        assert_eq!(options.paste_format(&content), PasteFormat::Markdown);
+    }
+}
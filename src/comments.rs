@@ -0,0 +1,129 @@
+use pbcli::privatebin::CommentNode;
+use std::time::{Duration, UNIX_EPOCH};
+
+const BOLD_CYAN: &str = "\x1b[1;36m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Render a comment tree as indented threads.
+/// Top level comments which are not direct replies to the paste, because their parent is
+/// missing, are marked with the id they reply to.
+pub(crate) fn render_threads(tree: &[CommentNode], paste_id: &str, color: bool) -> String {
+    let mut out = format!("\n--- {} comments ---\n", count(tree));
+    for node in tree {
+        let reply_to = (node.parent_id != paste_id).then_some(node.parent_id.as_str());
+        out.push('\n');
+        out.push_str(&render_comment(node, reply_to, 0, color));
+        render_replies(&node.replies, 1, color, &mut out);
+    }
+    out
+}
+
+fn count(nodes: &[CommentNode]) -> usize {
+    nodes.iter().map(|node| 1 + count(&node.replies)).sum()
+}
+
+fn render_replies(nodes: &[CommentNode], depth: usize, color: bool, out: &mut String) {
+    for node in nodes {
+        out.push('\n');
+        out.push_str(&render_comment(node, None, depth, color));
+        render_replies(&node.replies, depth + 1, color, out);
+    }
+}
+
+/// A single comment: a header with nickname, id and creation time, then the indented text
+pub(crate) fn render_comment(
+    node: &CommentNode,
+    reply_to: Option<&str>,
+    depth: usize,
+    color: bool,
+) -> String {
+    let indent = "    ".repeat(depth);
+    let arrow = if depth > 0 { "↳ " } else { "" };
+    let nickname = node.nickname.as_deref().unwrap_or("anonymous");
+    let time = node
+        .created
+        .and_then(|secs| u64::try_from(secs).ok())
+        .map(|secs| {
+            humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(secs)).to_string()
+        })
+        .unwrap_or_default();
+    let reply_to = reply_to
+        .map(|parent| format!(" in reply to {parent}"))
+        .unwrap_or_default();
+    let (bold, dim, red, reset) = if color {
+        (BOLD_CYAN, DIM, RED, RESET)
+    } else {
+        ("", "", "", "")
+    };
+
+    let mut out = format!(
+        "{indent}{arrow}{bold}{nickname}{reset} {dim}{id} {time}{reply_to}{reset}\n",
+        id = node.id
+    );
+    let body_indent = format!("{indent}{}", " ".repeat(arrow.chars().count()));
+    match (&node.comment, &node.decrypt_error) {
+        (Some(comment), _) => {
+            for line in comment.lines() {
+                out.push_str(&format!("{body_indent}{line}\n"));
+            }
+        }
+        (None, error) => {
+            let error = error.as_deref().unwrap_or_default();
+            out.push_str(&format!(
+                "{body_indent}{red}[could not decrypt: {error}]{reset}\n"
+            ));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_threads_and_orphans() {
+        let tree = vec![
+            CommentNode::sample(
+                "a",
+                "paste",
+                vec![CommentNode::sample(
+                    "b",
+                    "a",
+                    vec![CommentNode::sample("c", "b", vec![])],
+                )],
+            ),
+            // reply to a comment which is not on the paste
+            CommentNode::sample("d", "missing", vec![]),
+        ];
+        let rendered = render_threads(&tree, "paste", false);
+        let expected = "
+--- 4 comments ---
+
+anonymous a 1970-01-01T00:00:00Z
+text of a
+
+    ↳ anonymous b 1970-01-01T00:00:00Z
+      text of b
+
+        ↳ anonymous c 1970-01-01T00:00:00Z
+          text of c
+
+anonymous d 1970-01-01T00:00:00Z in reply to missing
+text of d
+";
+        assert_eq!(rendered, expected);
+    }
+
+    #[test]
+    fn renders_failed_comments() {
+        let mut failed = CommentNode::sample("a", "paste", vec![]);
+        failed.comment = None;
+        failed.decrypt_error = Some("aes error".into());
+        let rendered = render_threads(&[failed], "paste", false);
+        assert!(rendered.starts_with("\n--- 1 comments ---\n"));
+        assert!(rendered.contains("[could not decrypt: aes error]"));
+    }
+}
@@ -1,7 +1,14 @@
 use crate::error::{PasteError, PbResult};
-use crate::privatebin::{Cipher, CompressionType};
+use crate::privatebin::{Cipher, CompressionType, MAX_COMPRESSION_LEVEL};
+use crate::progress::{report, Progress, ProgressListener, ProgressPhase};
 use aes_gcm::aead::{Aead, NewAead};
 use aes_gcm::{Key, Nonce};
+use miniz_oxide::deflate::core::{
+    compress, create_comp_flags_from_zip_params, CompressorOxide, TDEFLFlush, TDEFLStatus,
+};
+use miniz_oxide::inflate::core::{decompress, inflate_flags, DecompressorOxide};
+use miniz_oxide::inflate::TINFLStatus;
+use zeroize::Zeroizing;
 
 /// Trait implemented by any decrypt-able type (paste or comment)
 pub trait Decryptable {
@@ -15,6 +22,48 @@ pub trait Decryptable {
     fn get_cipher(&self) -> &Cipher;
 }
 
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: u64 = 100 * 1024 * 1024;
+pub const DEFAULT_MAX_KDF_ITERATIONS: u32 = 1_000_000;
+
+/// Input is (de)compressed in chunks of this size, reporting progress after each
+const PROGRESS_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Upper bounds on the work a paste can cause while it is decrypted.
+/// Protects against decompression bombs and excessive key derivation cost.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct DecryptionLimits {
+    #[cfg_attr(feature = "uniffi", uniffi(default = 104857600))]
+    pub max_decompressed_size: u64,
+    #[cfg_attr(feature = "uniffi", uniffi(default = 1000000))]
+    pub max_kdf_iterations: u32,
+}
+
+impl Default for DecryptionLimits {
+    fn default() -> Self {
+        Self {
+            max_decompressed_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+            max_kdf_iterations: DEFAULT_MAX_KDF_ITERATIONS,
+        }
+    }
+}
+
+fn kdf_iterations(cipher: &Cipher) -> PbResult<std::num::NonZeroU32> {
+    std::num::NonZeroU32::new(cipher.kdf_iterations).ok_or_else(|| {
+        PasteError::InvalidCipherParameters("kdf iterations must not be zero".into())
+    })
+}
+
+fn nonce_from_slice(nonce: &[u8]) -> PbResult<&Nonce<typenum::U16>> {
+    if nonce.len() != 16 {
+        return Err(PasteError::InvalidCipherParameters(format!(
+            "expected a 16 byte iv, got {} bytes",
+            nonce.len()
+        )));
+    }
+    Ok(Nonce::from_slice(nonce))
+}
+
 fn derive_key(iterations: std::num::NonZeroU32, salt: &[u8], key: &[u8], out: &mut [u8]) {
     ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, key, out);
 }
@@ -24,6 +73,18 @@ pub fn decrypt_with_password<DecryptedT: serde::de::DeserializeOwned>(
     decryptable: &impl Decryptable,
     key: &[u8],
     password: &str,
+    limits: DecryptionLimits,
+) -> PbResult<DecryptedT> {
+    decrypt_with_progress(decryptable, key, password, limits, None)
+}
+
+/// Same as `decrypt_with_password`, reporting key derivation, decryption and decompression
+pub fn decrypt_with_progress<DecryptedT: serde::de::DeserializeOwned>(
+    decryptable: &impl Decryptable,
+    key: &[u8],
+    password: &str,
+    limits: DecryptionLimits,
+    progress: Progress,
 ) -> PbResult<DecryptedT> {
     let cipher_algo = &decryptable.get_cipher().cipher_algo;
     let cipher_mode = &decryptable.get_cipher().cipher_mode;
@@ -31,18 +92,31 @@ pub fn decrypt_with_password<DecryptedT: serde::de::DeserializeOwned>(
     let compression_type = &decryptable.get_cipher().compression_type;
 
     let salt = &decryptable.get_cipher().vec_kdf_salt()?;
-    let iterations = std::num::NonZeroU32::new(decryptable.get_cipher().kdf_iterations).unwrap();
+    let iterations = kdf_iterations(decryptable.get_cipher())?;
+    if iterations.get() > limits.max_kdf_iterations {
+        return Err(PasteError::KdfIterationsExceeded {
+            iterations: iterations.get(),
+            limit: limits.max_kdf_iterations,
+        });
+    }
 
-    let key = [key, password.as_bytes()].concat();
+    let key = Zeroizing::new([key, password.as_bytes()].concat());
 
-    let mut derived_key = [0u8; 32];
-    derive_key(iterations, salt, &key, &mut derived_key);
+    let mut derived_key = Zeroizing::new([0u8; 32]);
+    report(progress, ProgressPhase::KeyDerivation, 0, Some(1));
+    derive_key(iterations, salt, &key, derived_key.as_mut());
+    report(progress, ProgressPhase::KeyDerivation, 1, Some(1));
 
     match (&cipher_algo[..], &cipher_mode[..], kdf_keysize) {
         ("aes", "gcm", 256) => {
-            let data = decrypt_aes_256_gcm(decryptable, &derived_key, compression_type)?;
-            let value: serde_json::Value = serde_json::from_slice(&data)?;
-            Ok(serde_json::from_value(value)?)
+            let data = decrypt_aes_256_gcm(
+                decryptable,
+                derived_key.as_ref(),
+                compression_type,
+                limits.max_decompressed_size,
+                progress,
+            )?;
+            Ok(serde_json::from_slice(&data)?)
         }
         _ => Err(PasteError::CipherNotImplemented {
             cipher_mode: decryptable.get_cipher().cipher_mode.clone(),
@@ -56,30 +130,49 @@ pub fn encrypt(
     content: &str,
     key: &[u8],
     password: &str,
-    salt: &[u8],
-    nonce: &[u8],
-    iterations: u32,
+    cipher: &Cipher,
     aad: &str,
+    compression_level: u8,
+    progress: Option<&dyn ProgressListener>,
 ) -> PbResult<Vec<u8>> {
-    let paste_blob = miniz_oxide::deflate::compress_to_vec(content.as_bytes(), 10);
+    let salt = cipher.vec_kdf_salt()?;
+    let nonce = cipher.vec_cipher_iv()?;
 
-    let key = [key, password.as_bytes()].concat();
+    let paste_blob = match cipher.compression_type {
+        CompressionType::None => Zeroizing::new(content.as_bytes().to_vec()),
+        CompressionType::Zlib => {
+            if compression_level > MAX_COMPRESSION_LEVEL {
+                return Err(PasteError::InvalidCipherParameters(format!(
+                    "compression level must be between 0 and {}",
+                    MAX_COMPRESSION_LEVEL
+                )));
+            }
+            deflate(content.as_bytes(), compression_level, progress)?
+        }
+    };
 
-    let mut derived_key = [0u8; 32];
-    derive_key(
-        std::num::NonZeroU32::new(iterations).unwrap(),
-        salt,
-        &key,
-        &mut derived_key,
-    );
+    let key = Zeroizing::new([key, password.as_bytes()].concat());
 
-    type Cipher = aes_gcm::AesGcm<aes_gcm::aes::Aes256, typenum::U16>;
-    let cipher = Cipher::new(Key::from_slice(&derived_key));
+    let mut derived_key = Zeroizing::new([0u8; 32]);
+    report(progress, ProgressPhase::KeyDerivation, 0, Some(1));
+    derive_key(kdf_iterations(cipher)?, &salt, &key, derived_key.as_mut());
+    report(progress, ProgressPhase::KeyDerivation, 1, Some(1));
+
+    let blob_len = paste_blob.len() as u64;
+    report(progress, ProgressPhase::Encryption, 0, Some(blob_len));
+    type AesCipher = aes_gcm::AesGcm<aes_gcm::aes::Aes256, typenum::U16>;
+    let aes = AesCipher::new(Key::from_slice(derived_key.as_ref()));
     let payload = aes_gcm::aead::Payload {
         msg: &paste_blob,
         aad: aad.as_bytes(),
     };
-    let encrypted_data = cipher.encrypt(Nonce::from_slice(nonce), payload)?;
+    let encrypted_data = aes.encrypt(nonce_from_slice(&nonce)?, payload)?;
+    report(
+        progress,
+        ProgressPhase::Encryption,
+        blob_len,
+        Some(blob_len),
+    );
 
     Ok(encrypted_data)
 }
@@ -88,7 +181,9 @@ fn decrypt_aes_256_gcm(
     decryptable: &impl Decryptable,
     derived_key: &[u8],
     compression_type: &CompressionType,
-) -> PbResult<Vec<u8>> {
+    max_decompressed_size: u64,
+    progress: Progress,
+) -> PbResult<Zeroizing<Vec<u8>>> {
     type Cipher = aes_gcm::AesGcm<aes_gcm::aes::Aes256, typenum::U16>;
     let ciphertext = base64::decode(decryptable.get_ct())?;
     let nonce = decryptable.get_cipher().vec_cipher_iv()?;
@@ -99,10 +194,227 @@ fn decrypt_aes_256_gcm(
         msg: &ciphertext,
         aad: adata_str.as_bytes(),
     };
-    let data = cipher.decrypt(Nonce::from_slice(&nonce), payload)?;
+    let ciphertext_len = ciphertext.len() as u64;
+    report(progress, ProgressPhase::Decryption, 0, Some(ciphertext_len));
+    let data = Zeroizing::new(cipher.decrypt(nonce_from_slice(&nonce)?, payload)?);
+    report(
+        progress,
+        ProgressPhase::Decryption,
+        ciphertext_len,
+        Some(ciphertext_len),
+    );
     let decompressed = match compression_type {
         CompressionType::None => data,
-        CompressionType::Zlib => miniz_oxide::inflate::decompress_to_vec(&data)?,
+        CompressionType::Zlib => inflate(&data, max_decompressed_size, progress)?,
     };
     Ok(decompressed)
 }
+
+/// Grow a buffer without leaving copies of its content in freed memory
+fn grow(buffer: Zeroizing<Vec<u8>>, used: usize, new_len: usize) -> Zeroizing<Vec<u8>> {
+    let mut grown = Zeroizing::new(vec![0u8; new_len]);
+    grown[..used].copy_from_slice(&buffer[..used]);
+    grown
+}
+
+/// Raw deflate, as used by PrivateBin
+fn deflate(input: &[u8], level: u8, progress: Progress) -> PbResult<Zeroizing<Vec<u8>>> {
+    let flags = create_comp_flags_from_zip_params(level.into(), 0, 0);
+    let mut compressor = CompressorOxide::new(flags);
+    let mut output = Zeroizing::new(vec![0u8; (input.len() / 2).max(64)]);
+    let total = input.len() as u64;
+    let mut in_pos = 0;
+    let mut out_pos = 0;
+
+    report(progress, ProgressPhase::Compression, 0, Some(total));
+    loop {
+        let chunk_end = (in_pos + PROGRESS_CHUNK_SIZE).min(input.len());
+        let flush = if chunk_end == input.len() {
+            TDEFLFlush::Finish
+        } else {
+            TDEFLFlush::None
+        };
+        let (status, bytes_in, bytes_out) = compress(
+            &mut compressor,
+            &input[in_pos..chunk_end],
+            &mut output[out_pos..],
+            flush,
+        );
+        in_pos += bytes_in;
+        out_pos += bytes_out;
+        report(
+            progress,
+            ProgressPhase::Compression,
+            in_pos as u64,
+            Some(total),
+        );
+
+        match status {
+            TDEFLStatus::Done => {
+                output.truncate(out_pos);
+                return Ok(output);
+            }
+            // the chunk was not consumed or not flushed, the output is full
+            TDEFLStatus::Okay if in_pos < chunk_end || flush == TDEFLFlush::Finish => {
+                let new_len = output.len() * 2;
+                output = grow(output, out_pos, new_len);
+            }
+            TDEFLStatus::Okay => {}
+            status => {
+                return Err(PasteError::InvalidCipherParameters(format!(
+                    "compression failed: {status:?}"
+                )))
+            }
+        }
+    }
+}
+
+/// Inflate raw deflate data, refusing to produce more than `max_size` bytes
+fn inflate(input: &[u8], max_size: u64, progress: Progress) -> PbResult<Zeroizing<Vec<u8>>> {
+    let max_output = usize::try_from(max_size).unwrap_or(usize::MAX);
+    let mut decompressor = Box::<DecompressorOxide>::default();
+    let mut output = Zeroizing::new(vec![0u8; input.len().saturating_mul(2).min(max_output)]);
+    let total = input.len() as u64;
+    let mut in_pos = 0;
+    let mut out_pos = 0;
+
+    report(progress, ProgressPhase::Decompression, 0, Some(total));
+    loop {
+        let chunk_end = (in_pos + PROGRESS_CHUNK_SIZE).min(input.len());
+        let mut flags = inflate_flags::TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF;
+        if chunk_end < input.len() {
+            flags |= inflate_flags::TINFL_FLAG_HAS_MORE_INPUT;
+        }
+        let (status, bytes_in, bytes_out) = decompress(
+            &mut decompressor,
+            &input[in_pos..chunk_end],
+            &mut output,
+            out_pos,
+            flags,
+        );
+        in_pos += bytes_in;
+        out_pos += bytes_out;
+        report(
+            progress,
+            ProgressPhase::Decompression,
+            in_pos as u64,
+            Some(total),
+        );
+
+        match status {
+            TINFLStatus::Done => {
+                output.truncate(out_pos);
+                return Ok(output);
+            }
+            TINFLStatus::NeedsMoreInput if chunk_end < input.len() => {}
+            TINFLStatus::HasMoreOutput => {
+                if output.len() >= max_output {
+                    return Err(PasteError::DecompressedSizeExceeded { limit: max_size });
+                }
+                let new_len = output.len().saturating_mul(2).max(64).min(max_output);
+                output = grow(output, out_pos, new_len);
+            }
+            status => return Err(PasteError::Zlib(status)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::privatebin::{Comment, DEFAULT_COMPRESSION_LEVEL, DEFAULT_KDF_ITERATIONS};
+
+    const KEY: &[u8] = &[7u8; 32];
+
+    fn encrypted_comment(content: &str, kdf_iterations: u32) -> Comment {
+        let mut comment = Comment {
+            adata: Cipher::new(kdf_iterations, CompressionType::Zlib),
+            ..Default::default()
+        };
+        let ct = encrypt(
+            content,
+            KEY,
+            "",
+            &comment.adata,
+            &comment.get_adata_str(),
+            DEFAULT_COMPRESSION_LEVEL,
+            None,
+        )
+        .unwrap();
+        comment.ct = base64::encode(ct);
+        comment
+    }
+
+    #[test]
+    fn deflate_inflate_roundtrip() {
+        let large = "0123456789abcdef".repeat(3 * PROGRESS_CHUNK_SIZE / 16 + 5);
+        for input in ["", "a", large.as_str()] {
+            let compressed = deflate(input.as_bytes(), DEFAULT_COMPRESSION_LEVEL, None).unwrap();
+            let decompressed = inflate(&compressed, u64::MAX, None).unwrap();
+            assert_eq!(decompressed.as_slice(), input.as_bytes());
+        }
+    }
+
+    #[test]
+    fn inflate_rejects_zlib_bomb() {
+        let bomb = deflate(&vec![0u8; 64 * 1024 * 1024], 10, None).unwrap();
+        assert!(bomb.len() < 128 * 1024);
+
+        let result = inflate(&bomb, 1024 * 1024, None);
+        assert!(matches!(
+            result,
+            Err(PasteError::DecompressedSizeExceeded { limit }) if limit == 1024 * 1024
+        ));
+    }
+
+    #[test]
+    fn decrypt_rejects_content_over_decompressed_size_limit() {
+        let content = "a".repeat(2 * 1024 * 1024);
+        let json = serde_json::to_string(&content).unwrap();
+        let comment = encrypted_comment(&json, DEFAULT_KDF_ITERATIONS);
+        let limits = DecryptionLimits {
+            max_decompressed_size: 1024 * 1024,
+            ..Default::default()
+        };
+
+        let result = decrypt_with_password::<String>(&comment, KEY, "", limits);
+        assert!(matches!(
+            result,
+            Err(PasteError::DecompressedSizeExceeded { .. })
+        ));
+
+        let decrypted: String =
+            decrypt_with_password(&comment, KEY, "", DecryptionLimits::default()).unwrap();
+        assert_eq!(decrypted, content);
+    }
+
+    #[test]
+    fn decrypt_rejects_kdf_iterations_over_limit() {
+        let comment = encrypted_comment("\"text\"", DEFAULT_KDF_ITERATIONS);
+        let limits = DecryptionLimits {
+            max_kdf_iterations: DEFAULT_KDF_ITERATIONS - 1,
+            ..Default::default()
+        };
+
+        let result = decrypt_with_password::<String>(&comment, KEY, "", limits);
+        assert!(matches!(
+            result,
+            Err(PasteError::KdfIterationsExceeded { iterations, limit })
+                if iterations == DEFAULT_KDF_ITERATIONS && limit == DEFAULT_KDF_ITERATIONS - 1
+        ));
+    }
+
+    #[test]
+    fn decrypt_checks_kdf_iterations_before_deriving_the_key() {
+        // deriving a key with u32::MAX iterations would take hours
+        let mut comment = encrypted_comment("\"text\"", DEFAULT_KDF_ITERATIONS);
+        comment.adata.kdf_iterations = u32::MAX;
+
+        let result =
+            decrypt_with_password::<String>(&comment, KEY, "", DecryptionLimits::default());
+        assert!(matches!(
+            result,
+            Err(PasteError::KdfIterationsExceeded { .. })
+        ));
+    }
+}
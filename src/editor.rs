@@ -0,0 +1,91 @@
+use pbcli::{PasteError, PbResult};
+use rand_core::{OsRng, RngCore};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use zeroize::Zeroizing;
+
+/// Temporary file which is overwritten and removed when dropped
+struct ComposeFile {
+    path: PathBuf,
+}
+
+impl ComposeFile {
+    fn create() -> PbResult<(Self, std::fs::File)> {
+        let mut suffix = [0u8; 8];
+        OsRng.fill_bytes(&mut suffix);
+        let name = format!(
+            "pbcli-{}.txt",
+            suffix
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<String>()
+        );
+        let path = std::env::temp_dir().join(name);
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options.open(&path)?;
+        Ok((Self { path }, file))
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ComposeFile {
+    fn drop(&mut self) {
+        // best effort, the editor may have replaced the file
+        if let Ok(metadata) = std::fs::metadata(&self.path) {
+            let zeros = vec![0u8; metadata.len() as usize];
+            let _ = std::fs::write(&self.path, zeros);
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "notepad".into()
+            } else {
+                "vi".into()
+            }
+        })
+}
+
+/// Let the user write `what` in their editor.
+/// Fails if the editor exits with an error or the buffer is left empty.
+pub(crate) fn compose(what: &str) -> PbResult<String> {
+    let (file, mut handle) = ComposeFile::create()?;
+    handle.flush()?;
+    drop(handle);
+
+    let editor = editor_command();
+    let mut args = editor.split_whitespace();
+    let program = args.next().unwrap_or_default();
+    log::debug!("composing {what} with {program}");
+    let status = Command::new(program)
+        .args(args)
+        .arg(file.path())
+        .status()
+        .map_err(|e| PasteError::EditorFailed(format!("failed to start {program}: {e}")))?;
+    if !status.success() {
+        return Err(PasteError::EditorFailed(format!(
+            "{program} exited with {status}"
+        )));
+    }
+
+    let content = Zeroizing::new(std::fs::read_to_string(file.path())?);
+    if content.trim().is_empty() {
+        return Err(PasteError::EditorAborted);
+    }
+    Ok(content.to_string())
+}
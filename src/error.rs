@@ -1,4 +1,5 @@
 use base64::DecodeError;
+use data_url::forgiving_base64::InvalidBase64;
 use data_url::DataUrlError;
 use log::SetLoggerError;
 use miniz_oxide::inflate::TINFLStatus;
@@ -19,11 +20,14 @@ pub enum PasteError {
         keysize: u32,
     },
     MissingDecryptionKey,
+    MissingPasteId,
     // BadUrl,
-    PasteNotFound,
+    /// The message reported by the server, if any
+    PasteNotFound(Option<String>),
     InvalidData,
     UnknownPasteStatus(u32),
     Json(serde_json::error::Error),
+    Yaml(serde_yaml::Error),
     Request(reqwest::Error),
     Io(std::io::Error),
     ParseError(url::ParseError),
@@ -32,12 +36,64 @@ pub enum PasteError {
     Aes(aes_gcm::Error),
     Zlib(miniz_oxide::inflate::TINFLStatus),
     InvalidAttachment(data_url::DataUrlError),
+    InvalidAttachmentData(InvalidBase64),
     FileExists,
     NotAFile,
     InvalidTokenType(String),
     OidcBadRequest(serde_json::Value),
+    OidcInvalidResponse(String),
+    MissingOption(String),
+    UnknownTemplatePreset(String),
+    QrCode(qrcode::types::QrError),
+    UnsupportedQrFileType(String),
+    EditorFailed(String),
+    EditorAborted,
+    EmptyBody(String),
+    CommandFailed(String),
+    InvalidRedactRule(String),
+    WatchBurnAfterReading,
+    WatchMayBurn,
+    CommentDecryption {
+        id: String,
+        reason: String,
+    },
+    WatchNoDiscussion,
+    SensitiveContent(usize),
     LoggerInit(log::SetLoggerError),
     InvalidCertificate(String),
+    UnrecognizedInstance,
+    PasteTooLarge {
+        size: u64,
+        limit: u64,
+    },
+    SizeLimitExceeded {
+        size: u64,
+        limit: u64,
+    },
+    UnsupportedExpiry {
+        expire: String,
+        supported: Vec<String>,
+    },
+    UnsupportedFormat(String),
+    FeatureDisabled(String),
+    ServerError {
+        http_status: u16,
+        message: String,
+    },
+    UnexpectedResponse {
+        http_status: u16,
+        excerpt: String,
+    },
+    InvalidCipherParameters(String),
+    KdfIterationsExceeded {
+        iterations: u32,
+        limit: u32,
+    },
+    DecompressedSizeExceeded {
+        limit: u64,
+    },
+    BurnAfterReading,
+    VerifyMayBurn,
 }
 
 impl std::error::Error for PasteError {}
@@ -55,6 +111,7 @@ impl fmt::Display for PasteError {
                 cipher_algo, cipher_mode, keysize
             ),
             PasteError::Json(r) => r.fmt(f),
+            PasteError::Yaml(r) => r.fmt(f),
             PasteError::Request(r) => r.fmt(f),
             PasteError::Io(r) => r.fmt(f),
             PasteError::ParseError(r) => r.fmt(f),
@@ -63,21 +120,120 @@ impl fmt::Display for PasteError {
             PasteError::Zlib(err) => write!(f, "Zlib error: {:?}", err),
             PasteError::Base58Error(err) => err.fmt(f),
             PasteError::UnknownPasteStatus(err) => write!(f, "Unknown paste status: {}", err),
-            PasteError::PasteNotFound => write!(f, "Invalid paste ID"),
+            PasteError::PasteNotFound(None) => write!(f, "Invalid paste ID"),
+            PasteError::PasteNotFound(Some(message)) => write!(f, "Paste not found: {}", message),
             PasteError::MissingDecryptionKey => write!(f, "Missing decryption key"),
+            PasteError::MissingPasteId => write!(f, "Missing paste id"),
             // PasteError::BadUrl => write!(f, "Badly formatted url"),
             PasteError::InvalidData => write!(f, "Invalid Data"),
             PasteError::InvalidAttachment(err) => write!(f, "Invalid attachment: {:?}", err),
+            PasteError::InvalidAttachmentData(err) => {
+                write!(f, "Invalid attachment data: {:?}", err)
+            }
             PasteError::FileExists => write!(f, "File already exists. Use --overwrite to force"),
             PasteError::NotAFile => write!(f, "Given path is not a file"),
             PasteError::InvalidTokenType(token_type) => {
                 write!(f, "Invalid token type: {}", token_type)
             }
             PasteError::OidcBadRequest(json) => write!(f, "{}", json),
+            PasteError::OidcInvalidResponse(msg) => write!(f, "Invalid oidc response: {}", msg),
+            PasteError::MissingOption(name) => write!(f, "Missing option: {}", name),
+            PasteError::EditorFailed(reason) => write!(f, "Editor failed: {}", reason),
+            PasteError::EditorAborted => write!(f, "Aborting, nothing was written in the editor"),
+            PasteError::EmptyBody(what) => write!(f, "Refusing to post an empty {}", what),
+            PasteError::CommandFailed(reason) => write!(f, "Command failed: {}", reason),
+            PasteError::InvalidRedactRule(rule) => write!(f, "Invalid redact rule: {}", rule),
+            PasteError::SensitiveContent(count) => {
+                write!(f, "The paste contains {} sensitive values", count)
+            }
+            PasteError::CommentDecryption { id, reason } => {
+                write!(f, "Comment {} could not be decrypted: {}", id, reason)
+            }
+            PasteError::WatchBurnAfterReading => {
+                write!(f, "Burn after reading pastes can not be watched")
+            }
+            PasteError::WatchMayBurn => {
+                write!(f, "Watching destroys the paste if it is burn after reading")
+            }
+            PasteError::WatchNoDiscussion => {
+                write!(
+                    f,
+                    "The paste has discussion disabled, there is nothing to watch"
+                )
+            }
+            PasteError::QrCode(r) => r.fmt(f),
+            PasteError::UnsupportedQrFileType(extension) => write!(
+                f,
+                "Unsupported qr code file type '{}', use .png or .svg",
+                extension
+            ),
+            PasteError::UnknownTemplatePreset(name) => {
+                write!(f, "No template preset named '{}' is defined", name)
+            }
             PasteError::LoggerInit(err) => {
                 write!(f, "Failed to init logger: {}", err)
             }
             PasteError::InvalidCertificate(msg) => write!(f, "{}", msg),
+            PasteError::UnrecognizedInstance => {
+                write!(f, "Host does not look like a PrivateBin instance")
+            }
+            PasteError::PasteTooLarge { size, limit } => write!(
+                f,
+                "Paste is {} bytes, but the instance only accepts up to {} bytes",
+                size, limit
+            ),
+            PasteError::SizeLimitExceeded { size, limit } => write!(
+                f,
+                "Paste is {} bytes, more than the size limit of {} bytes",
+                size, limit
+            ),
+            PasteError::UnsupportedExpiry { expire, supported } => write!(
+                f,
+                "Expiry {} is not supported by the instance. Supported: {}",
+                expire,
+                supported.join(", ")
+            ),
+            PasteError::UnsupportedFormat(format) => {
+                write!(f, "Format {} is not supported by the instance", format)
+            }
+            PasteError::FeatureDisabled(feature) => {
+                write!(f, "The instance has {} disabled", feature)
+            }
+            PasteError::ServerError {
+                http_status,
+                message,
+            } => write!(f, "Server error (HTTP {}): {}", http_status, message),
+            PasteError::UnexpectedResponse {
+                http_status,
+                excerpt,
+            } => write!(
+                f,
+                "Unexpected response from server (HTTP {}): {}",
+                http_status, excerpt
+            ),
+            PasteError::InvalidCipherParameters(msg) => {
+                write!(f, "Invalid cipher parameters: {}", msg)
+            }
+            PasteError::KdfIterationsExceeded { iterations, limit } => write!(
+                f,
+                "Paste requires {} kdf iterations, the limit is {}",
+                iterations, limit
+            ),
+            PasteError::BurnAfterReading => write!(
+                f,
+                "Paste is marked burn after reading, fetching it would destroy it"
+            ),
+            PasteError::VerifyMayBurn => {
+                write!(
+                    f,
+                    "Verifying destroys the paste if it is burn after reading"
+                )
+            }
+            PasteError::DecompressedSizeExceeded { limit } => write!(
+                f,
+                "Paste decompresses to more than the limit of {} bytes",
+                limit
+            ),
         }
     }
 }
@@ -94,6 +250,18 @@ impl From<serde_json::Error> for PasteError {
     }
 }
 
+impl From<qrcode::types::QrError> for PasteError {
+    fn from(err: qrcode::types::QrError) -> Self {
+        PasteError::QrCode(err)
+    }
+}
+
+impl From<serde_yaml::Error> for PasteError {
+    fn from(err: serde_yaml::Error) -> Self {
+        PasteError::Yaml(err)
+    }
+}
+
 impl From<url::ParseError> for PasteError {
     fn from(err: url::ParseError) -> Self {
         PasteError::ParseError(err)
@@ -136,6 +304,12 @@ impl From<DataUrlError> for PasteError {
     }
 }
 
+impl From<InvalidBase64> for PasteError {
+    fn from(err: InvalidBase64) -> Self {
+        PasteError::InvalidAttachmentData(err)
+    }
+}
+
 impl From<SetLoggerError> for PasteError {
     fn from(err: SetLoggerError) -> Self {
         PasteError::LoggerInit(err)
@@ -0,0 +1,223 @@
+/// A source code language, recognized by file extension or by keywords in the text
+#[derive(Debug, PartialEq, Eq)]
+pub struct Language {
+    /// Name of the syntax used for highlighting
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    /// Keywords hinting at the language, at least two have to match
+    pub hints: &'static [&'static str],
+}
+
+const MIN_HINTS: usize = 2;
+
+pub const LANGUAGES: &[Language] = &[
+    Language {
+        name: "Rust",
+        extensions: &["rs"],
+        hints: &["fn ", "let mut ", "impl ", "pub fn ", "use std::", "::new("],
+    },
+    Language {
+        name: "Python",
+        extensions: &["py"],
+        hints: &["def ", "import ", "elif ", "self.", "__init__", "print("],
+    },
+    Language {
+        name: "C",
+        extensions: &["c", "h"],
+        hints: &[],
+    },
+    Language {
+        name: "C++",
+        extensions: &["cc", "cpp", "hpp"],
+        hints: &["#include", "std::", "nullptr", "template<", "int main("],
+    },
+    Language {
+        name: "C#",
+        extensions: &["cs"],
+        hints: &[],
+    },
+    Language {
+        name: "CSS",
+        extensions: &["css"],
+        hints: &[],
+    },
+    Language {
+        name: "Diff",
+        extensions: &["diff", "patch"],
+        hints: &[],
+    },
+    Language {
+        name: "Go",
+        extensions: &["go"],
+        hints: &["package ", "func ", ":= ", "fmt."],
+    },
+    Language {
+        name: "JavaScript",
+        extensions: &["js", "jsx"],
+        hints: &["function ", "const ", "=> ", "console.log", "require("],
+    },
+    Language {
+        name: "TypeScript",
+        extensions: &["ts", "tsx"],
+        hints: &[],
+    },
+    Language {
+        name: "Java",
+        extensions: &["java"],
+        hints: &["public class ", "System.out", "private ", "void "],
+    },
+    Language {
+        name: "Kotlin",
+        extensions: &["kt"],
+        hints: &[],
+    },
+    Language {
+        name: "Scala",
+        extensions: &["scala"],
+        hints: &[],
+    },
+    Language {
+        name: "Swift",
+        extensions: &["swift"],
+        hints: &[],
+    },
+    Language {
+        name: "HTML",
+        extensions: &["html"],
+        hints: &["<div", "<html", "<body", "<span", "</"],
+    },
+    Language {
+        name: "XML",
+        extensions: &["xml"],
+        hints: &[],
+    },
+    Language {
+        name: "JSON",
+        extensions: &["json"],
+        hints: &[],
+    },
+    Language {
+        name: "SQL",
+        extensions: &["sql"],
+        hints: &["SELECT ", "FROM ", "WHERE ", "INSERT INTO", "CREATE TABLE"],
+    },
+    Language {
+        name: "YAML",
+        extensions: &["yaml", "yml"],
+        hints: &["---\n", ":\n  ", "- name:"],
+    },
+    Language {
+        name: "TOML",
+        extensions: &["toml"],
+        hints: &[],
+    },
+    Language {
+        name: "INI",
+        extensions: &["ini", "cfg", "conf"],
+        hints: &[],
+    },
+    Language {
+        name: "Bourne Again Shell (bash)",
+        extensions: &["sh", "bash", "zsh"],
+        hints: &["echo ", "fi\n", "then\n", "done\n", "$("],
+    },
+    Language {
+        name: "PowerShell",
+        extensions: &["ps1"],
+        hints: &[],
+    },
+    Language {
+        name: "Lua",
+        extensions: &["lua"],
+        hints: &[],
+    },
+    Language {
+        name: "Perl",
+        extensions: &["pl"],
+        hints: &[],
+    },
+    Language {
+        name: "PHP",
+        extensions: &["php"],
+        hints: &[],
+    },
+    Language {
+        name: "Ruby",
+        extensions: &["rb"],
+        hints: &[],
+    },
+];
+
+impl Language {
+    /// Language of a file name by its extension, ignoring case
+    pub fn from_file_name(file_name: &str) -> Option<&'static Language> {
+        let extension = std::path::Path::new(file_name)
+            .extension()?
+            .to_string_lossy()
+            .to_ascii_lowercase();
+        LANGUAGES
+            .iter()
+            .find(|language| language.extensions.contains(&extension.as_str()))
+    }
+
+    /// Language whose keywords occur most often in the text
+    pub fn from_content(text: &str) -> Option<&'static Language> {
+        LANGUAGES
+            .iter()
+            .map(|language| {
+                let score = language
+                    .hints
+                    .iter()
+                    .filter(|hint| text.contains(*hint))
+                    .count();
+                (score, language)
+            })
+            .filter(|(score, _)| *score >= MIN_HINTS)
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, language)| language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_file_name() {
+        let name = |file_name| Language::from_file_name(file_name).map(|l| l.name);
+        assert_eq!(name("main.rs"), Some("Rust"));
+        assert_eq!(name("src/Main.RS"), Some("Rust"));
+        assert_eq!(name("deploy.yml"), Some("YAML"));
+        assert_eq!(name("fix.patch"), Some("Diff"));
+        assert_eq!(name("notes.md"), None);
+        assert_eq!(name("Makefile"), None);
+        assert_eq!(name(".rs"), None);
+    }
+
+    #[test]
+    fn from_content() {
+        let name = |text| Language::from_content(text).map(|l| l.name);
+        assert_eq!(
+            name("use std::io;\n\nfn main() {\n    let mut x = 1;\n}\n"),
+            Some("Rust")
+        );
+        assert_eq!(
+            name("import os\n\ndef main():\n    print(os.getcwd())\n"),
+            Some("Python")
+        );
+        assert_eq!(name("SELECT id FROM users WHERE id = 1"), Some("SQL"));
+        // a single keyword is not enough
+        assert_eq!(name("Let me import this tomorrow."), None);
+        assert_eq!(name(""), None);
+    }
+
+    #[test]
+    fn extensions_are_unique_and_lowercase() {
+        let mut extensions: Vec<_> = LANGUAGES.iter().flat_map(|l| l.extensions).collect();
+        assert!(extensions.iter().all(|e| e.to_ascii_lowercase() == **e));
+        let count = extensions.len();
+        extensions.sort();
+        extensions.dedup();
+        assert_eq!(extensions.len(), count);
+    }
+}
@@ -1,19 +1,30 @@
 pub mod api;
+pub mod cache;
+pub mod capabilities;
+pub mod capi;
+pub mod client;
 pub mod config;
 pub mod crypto;
 pub mod error;
+pub mod language;
 pub mod opts;
 pub mod privatebin;
+pub mod progress;
+pub mod secret;
 pub mod util;
 
 #[cfg(feature = "uniffi")]
 mod uniffi_custom_types;
 
 pub use api::API;
+pub use capabilities::InstanceCapabilities;
+pub use client::{ClientConfig, ClientConfigBuilder, CommentOptions, OidcAuth, PasteOptions};
 pub use error::{PasteError, PbResult};
 pub use opts::Opts;
-pub use privatebin::{DecryptedPaste, PasteFormat};
-pub use util::check_filesize;
+pub use privatebin::{CommentNode, DecryptedPaste, PasteFormat};
+pub use progress::{ProgressListener, ProgressPhase};
+pub use secret::SecretString;
+pub use util::{check_filesize, SizeLimitConfirmation};
 
 #[cfg(feature = "uniffi")]
 uniffi::setup_scaffolding!();
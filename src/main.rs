@@ -1,19 +1,38 @@
-use clap::Parser;
+use atomic_file::{write_atomically, AtomicFile};
+use clap::{Parser, ValueEnum};
 use data_url::DataUrl;
 use pbcli::api::API;
+use pbcli::cache::CapabilityCache;
 use pbcli::error::{PasteError, PbResult};
-use pbcli::opts::Opts;
-use pbcli::privatebin::{DecryptedComment, DecryptedCommentsMap, DecryptedPaste};
-use pbcli::util::check_filesize;
+use pbcli::opts::{Opts, OutputFormat};
+use pbcli::privatebin::{
+    CommentNode, DecryptedComment, DecryptedPaste, Paste, PasteFormat, PasteMetadata,
+};
+use pbcli::progress::ProgressListener;
+use pbcli::secret::SecretString;
+use pbcli::util::{check_filesize, SizeLimitConfirmation};
 use reqwest::blocking::Client;
 use scraper::{Html, Selector};
 use serde_json::Value;
 use std::ffi::OsString;
 use std::io::IsTerminal;
 use std::io::{Read, Write};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use term_progress::TerminalProgress;
+use url::Url;
+use zeroize::{Zeroize, Zeroizing};
 
+mod atomic_file;
+mod comments;
+mod editor;
 mod logger;
+mod pretty;
+mod qr;
+mod redact;
+mod template;
+mod term_progress;
 
 fn get_stdin() -> std::io::Result<String> {
     if std::io::stdin().is_terminal() {
@@ -24,6 +43,84 @@ fn get_stdin() -> std::io::Result<String> {
     Ok(buffer)
 }
 
+/// Run a command line through the platform shell
+fn shell_command(command: &str) -> std::process::Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = std::process::Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = std::process::Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+/// Text of a new paste or comment, built from --from-file and --from-command
+fn read_body_sources(opts: &Opts) -> PbResult<String> {
+    let mut parts: Vec<(String, Zeroizing<String>)> = vec![];
+    for path in &opts.from_file {
+        if path.as_os_str() == "-" {
+            parts.push(("stdin".into(), Zeroizing::new(get_stdin()?)));
+        } else {
+            let text = Zeroizing::new(std::fs::read_to_string(path)?);
+            parts.push((path.display().to_string(), text));
+        }
+    }
+
+    if let Some(command) = &opts.from_command {
+        let output = shell_command(command)
+            .stderr(std::process::Stdio::inherit())
+            .output()
+            .map_err(|e| PasteError::CommandFailed(format!("{command}: {e}")))?;
+        let stdout = Zeroizing::new(output.stdout);
+        if !output.status.success() {
+            return Err(PasteError::CommandFailed(format!(
+                "{command} exited with {}",
+                output.status
+            )));
+        }
+        let text = String::from_utf8(stdout.to_vec()).map_err(|_| {
+            PasteError::CommandFailed(format!("{command} did not print valid utf-8"))
+        })?;
+        parts.push((format!("$ {command}"), Zeroizing::new(text)));
+    }
+
+    if !opts.file_headers {
+        return Ok(parts.iter().map(|(_, text)| text.as_str()).collect());
+    }
+    let mut body = String::new();
+    for (i, (name, text)) in parts.iter().enumerate() {
+        if i > 0 {
+            body.push('\n');
+        }
+        body.push_str(&format!("==> {name} <==\n"));
+        body.push_str(text);
+        if !text.is_empty() && !text.ends_with('\n') {
+            body.push('\n');
+        }
+    }
+    Ok(body)
+}
+
+/// Text of a new paste or comment: the given files or command, stdin, or the editor
+/// when run interactively. With `required` unset an empty text is accepted as is.
+fn get_body(opts: &Opts, what: &str, required: bool) -> PbResult<String> {
+    let body = if !opts.from_file.is_empty() || opts.from_command.is_some() {
+        read_body_sources(opts)?
+    } else if required && std::io::stdin().is_terminal() {
+        return editor::compose(what);
+    } else {
+        get_stdin()?
+    };
+    if required && body.is_empty() && !opts.allow_empty {
+        return Err(PasteError::EmptyBody(what.to_string()));
+    }
+    Ok(body)
+}
+
 fn create_dataurl(path: &std::ffi::OsStr, data: String) -> String {
     let mime = mime_guess::from_path(path)
         .first()
@@ -31,73 +128,293 @@ fn create_dataurl(path: &std::ffi::OsStr, data: String) -> String {
     format!("data:{};base64,{}", mime.essence_str(), data)
 }
 
-fn handle_get(opts: &Opts) -> PbResult<()> {
-    let url = opts.get_url();
-    let paste_id = opts.get_url().query().unwrap();
-    let fragment = opts
-        .get_url()
-        .fragment()
-        .ok_or(PasteError::MissingDecryptionKey)?;
+/// Asks on the terminal before posting something larger than --size-limit.
+/// Declines without asking if stdin is not a terminal.
+struct SizeLimitPrompt;
+
+impl SizeLimitConfirmation for SizeLimitPrompt {
+    fn confirm_size(&self, _size: u64, _limit: u64) -> bool {
+        // the attachment and the encrypted paste are both checked, only ask once
+        static CONFIRMED: AtomicBool = AtomicBool::new(false);
+        if CONFIRMED.load(Ordering::Relaxed) {
+            return true;
+        }
+        if !std::io::stdin().is_terminal() {
+            return false;
+        }
+        let confirmed = dialoguer::Confirm::new()
+            .with_prompt("This paste exceeds your defined size limit. Continue?")
+            .interact()
+            .unwrap_or(false);
+        CONFIRMED.store(confirmed, Ordering::Relaxed);
+        confirmed
+    }
+}
+
+fn create_api(opts: &Opts, progress: &Option<Arc<TerminalProgress>>) -> PbResult<API> {
+    let progress = progress
+        .clone()
+        .map(|progress| progress as Arc<dyn ProgressListener>);
+    Ok(API::new_with_callbacks(
+        opts.get_url()?.clone(),
+        opts.client_config()?,
+        progress,
+        Some(Arc::new(SizeLimitPrompt)),
+    ))
+}
+
+/// Paste id and decryption key of a paste url, and whether the url carries
+/// the "warn before read" marker of burn after reading pastes.
+fn parse_paste_url(url: &Url) -> PbResult<(&str, &str, bool)> {
+    let paste_id = url.query().ok_or(PasteError::MissingPasteId)?;
+    let fragment = url.fragment().ok_or(PasteError::MissingDecryptionKey)?;
     // '-' character may be found at start of fragment. This should be stripped.
     // It is used to activate "warn before read" feature for burn on read pastes.
-    let key = fragment.strip_prefix('-').unwrap_or(fragment);
+    match fragment.strip_prefix('-') {
+        Some(key) => Ok((paste_id, key, true)),
+        None => Ok((paste_id, fragment, false)),
+    }
+}
 
-    let api = API::new(url.clone(), opts.clone());
-    let paste = api.get_paste(paste_id)?;
+fn fetch_paste(opts: &Opts, paste_id: &str) -> PbResult<Paste> {
+    let progress = TerminalProgress::for_stderr();
+    let api = create_api(opts, &progress)?;
+    let paste = api.get_paste(paste_id);
+    if let Some(progress) = &progress {
+        progress.finish();
+    }
+    paste
+}
 
-    let content: DecryptedPaste;
-    let comments: DecryptedCommentsMap;
+/// Decrypt a paste, asking for a password if needed and none was given.
+/// Also returns the password that worked, for decrypting the comments.
+fn decrypt_paste(
+    opts: &Opts,
+    paste: &Paste,
+    key: &str,
+) -> PbResult<(DecryptedPaste, SecretString)> {
+    let limits = opts.decryption_limits();
+    let decrypt = |password: &str| {
+        let progress = TerminalProgress::for_stderr();
+        let result = paste.decrypt_with_progress(
+            key,
+            password,
+            limits,
+            progress.clone().map(|p| p as Arc<dyn ProgressListener>),
+        );
+        if let Some(progress) = progress {
+            progress.finish();
+        }
+        result
+    };
 
     if let Some(pass) = &opts.password {
-        content = paste.decrypt_with_password(key, pass)?;
-        comments = paste.decrypt_comments_with_password(key, pass)?;
-    } else {
-        match paste.decrypt(key) {
-            Ok(c) => {
-                content = c;
-                comments = paste.decrypt_comments(key)?;
+        let content = decrypt(pass.expose_secret())?;
+        return Ok((content, pass.clone()));
+    }
+
+    match decrypt("") {
+        Ok(content) => Ok((content, SecretString::default())),
+        Err(err) => {
+            // only a failed authentication hints at a missing password
+            if !std::io::stdin().is_terminal() || !matches!(err, PasteError::Aes(_)) {
+                return Err(err);
             }
-            Err(err) => {
-                if !std::io::stdin().is_terminal() {
-                    return Err(err);
-                }
 
-                let password = dialoguer::Password::new()
+            let password = SecretString::new(
+                dialoguer::Password::new()
                     .with_prompt("Enter password")
-                    .interact()?;
-                content = paste.decrypt_with_password(key, &password)?;
-                comments = paste.decrypt_comments_with_password(key, &password)?;
+                    .interact()?,
+            );
+            let content = decrypt(password.expose_secret())?;
+            Ok((content, password))
+        }
+    }
+}
+
+/// Decrypted comment tree of a paste. Comments which do not decrypt are reported and kept
+/// marked in the tree, or fail the whole read with --strict-comments.
+fn comment_tree(
+    opts: &Opts,
+    paste: &Paste,
+    key: &str,
+    password: &SecretString,
+) -> PbResult<Vec<CommentNode>> {
+    fn failed<'a>(nodes: &'a [CommentNode], out: &mut Vec<&'a CommentNode>) {
+        for node in nodes {
+            if node.decrypt_error.is_some() {
+                out.push(node);
             }
+            failed(&node.replies, out);
         }
     }
 
-    if let (Some(attachment), Some(outfile)) = (content.attachment.as_ref(), opts.download.as_ref())
-    {
-        let url = DataUrl::process(attachment)?;
-        let (body, _) = url.decode_to_vec().unwrap();
+    let mut tree =
+        paste.comment_tree_with_limits(key, password.expose_secret(), opts.decryption_limits());
+    let mut failures = vec![];
+    failed(&tree, &mut failures);
 
-        if outfile.exists() && !opts.overwrite {
-            return Err(PasteError::FileExists);
+    if opts.strict_comments {
+        if let Some(node) = failures.first() {
+            let err = PasteError::CommentDecryption {
+                id: node.id.clone(),
+                reason: node.decrypt_error.clone().unwrap_or_default(),
+            };
+            tree.zeroize();
+            return Err(err);
         }
+    }
+    for node in failures {
+        eprintln!(
+            "warning: comment {} could not be decrypted: {}",
+            node.id,
+            node.decrypt_error.as_deref().unwrap_or_default()
+        );
+    }
+    Ok(tree)
+}
 
-        let mut handle = std::fs::File::create(outfile)?;
+/// The `comments` value of --json output: nested id, comment, nickname and replies.
+/// The comment is null if it could not be decrypted, `comment_tree` has the reason.
+fn comments_json(nodes: &[CommentNode]) -> Value {
+    nodes
+        .iter()
+        .map(|node| {
+            serde_json::json!({
+                "id": node.id,
+                "comment": node.comment,
+                "nickname": node.nickname,
+                "replies": comments_json(&node.replies),
+            })
+        })
+        .collect()
+}
+
+/// Ask before opening a paste whose url marks it as burn after reading.
+/// Without a terminal to ask on, --yes is required.
+fn confirm_burn_after_reading(opts: &Opts) -> PbResult<()> {
+    confirm_destructive_read(
+        opts,
+        "This paste will be deleted after reading it. Continue?",
+        PasteError::BurnAfterReading,
+    )
+}
 
-        handle.write_all(&body)?;
+/// Ask before fetching a paste that could be burn after reading, unless --yes was given
+fn confirm_destructive_read(opts: &Opts, prompt: &str, refusal: PasteError) -> PbResult<()> {
+    if opts.yes {
+        return Ok(());
     }
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Err(refusal);
+    }
+    let confirmed = dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?;
+    if !confirmed {
+        return Err(refusal);
+    }
+    Ok(())
+}
+
+fn handle_get(opts: &Opts) -> PbResult<()> {
+    let (paste_id, key, warn_before_read) = parse_paste_url(opts.get_url()?)?;
+    let template = opts.resolved_template()?.unwrap_or_default();
+    if warn_before_read {
+        confirm_burn_after_reading(opts)?;
+    }
+    // create the files before fetching, a burnt paste can not be fetched again
+    let save_to = opts
+        .save_to
+        .as_deref()
+        .map(|path| AtomicFile::create(path, opts.overwrite, true))
+        .transpose()?;
+    let download = opts
+        .download
+        .as_deref()
+        .map(|path| AtomicFile::create(path, opts.overwrite, false))
+        .transpose()?;
+    let paste = fetch_paste(opts, paste_id)?;
+    if paste.adata.burn == 1 && !warn_before_read && std::io::stderr().is_terminal() {
+        eprintln!("note: paste is burn after reading and has been deleted from the server");
+    }
+    let (mut content, password) = decrypt_paste(opts, &paste, key)?;
+    let mut comments = comment_tree(opts, &paste, key, &password)?;
+
+    // save before printing, so a broken pipe does not lose a burnt paste.
+    // A failed save still prints the paste and is reported afterwards.
+    let saved = save_to.map_or(Ok(()), |file| file.commit(content.paste.as_bytes()));
+
+    let downloaded = match (content.attachment.as_ref(), download) {
+        (Some(attachment), Some(file)) => DataUrl::process(attachment)
+            .map_err(PasteError::from)
+            .and_then(|url| Ok(Zeroizing::new(url.decode_to_vec()?.0)))
+            .and_then(|body| file.commit(&body)),
+        _ => Ok(()),
+    };
+
+    let output_format = opts.output_format();
+    // decoding the attachment again is only worth it if the metadata is shown
+    let metadata =
+        (opts.header || output_format != OutputFormat::Text).then(|| paste.metadata(&content));
+    if let Some(metadata) = metadata.as_ref().filter(|_| opts.header) {
+        eprintln!("{}", format_metadata_header(metadata));
+    }
+
+    if output_format == OutputFormat::Text {
+        let is_terminal = std::io::stdout().is_terminal();
+        // piped output stays the bare paste unless comments are asked for
+        let threads = if !comments.is_empty() && (is_terminal || opts.show_comments) {
+            let color = is_terminal && !opts.no_color && std::env::var_os("NO_COLOR").is_none();
+            let separator = if content.paste.ends_with('\n') {
+                ""
+            } else {
+                "\n"
+            };
+            let threads = comments::render_threads(&comments, &paste.id, color);
+            Zeroizing::new(format!("{separator}{threads}"))
+        } else {
+            Zeroizing::new(String::new())
+        };
 
-    if !opts.json {
-        std::io::stdout().write_all(content.paste.as_bytes())?;
+        if opts.pretty && is_terminal {
+            let mut rendered = Zeroizing::new(pretty::render(&content.paste, paste.adata.format));
+            rendered.push_str(&threads);
+            pretty::page(&rendered)?;
+        } else {
+            std::io::stdout().write_all(content.paste.as_bytes())?;
+            std::io::stdout().write_all(threads.as_bytes())?;
+        }
     } else {
-        let mut output: Value = serde_json::to_value(content)?;
+        let mut output: Value = serde_json::to_value(&content)?;
+        if let Some(metadata) = &metadata {
+            output["metadata"] = metadata_value(metadata)?;
+        }
         if !comments.is_empty() {
-            let comments_trees =
-                paste.comments_formatted_json_trees(&comments, &paste.comments_adjacency_map()?)?;
-            output["comments"] = serde_json::from_str(&comments_trees)?;
+            output["comments"] = comments_json(&comments);
+            output["comment_tree"] = serde_json::to_value(&comments)?;
         }
-        std::io::stdout().write_all(serde_json::to_string_pretty(&output)?.as_bytes())?;
+        let rendered = Zeroizing::new(match output_format {
+            OutputFormat::Yaml => serde_yaml::to_string(&output)?,
+            OutputFormat::Template => {
+                // metadata fields are available as top level placeholders
+                let mut values = output["metadata"].clone();
+                values["paste"] = output["paste"].take();
+                values["attachment_name"] = output["attachment_name"].take();
+                values["comments"] = output["comments"].take();
+                values["pasteurl"] = Value::String(opts.get_url()?.to_string());
+                template::render(&template, &values)
+            }
+            _ => serde_json::to_string_pretty(&output)?,
+        });
+        std::io::stdout().write_all(rendered.as_bytes())?;
     }
 
-    Ok(())
+    content.zeroize();
+    comments.zeroize();
+
+    saved.and(downloaded)
 }
 
 fn shorten_via_privatebin(opts: &Opts, long_url: &str) -> PbResult<String> {
@@ -105,22 +422,23 @@ fn shorten_via_privatebin(opts: &Opts, long_url: &str) -> PbResult<String> {
         let encoded = url::form_urlencoded::byte_serialize(long_url.as_bytes()).collect::<String>();
 
         // Always shorten on the same host as --host
-        let mut endpoint = opts.get_url().clone();
+        let mut endpoint = opts.get_url()?.clone();
         endpoint.set_fragment(None);
         endpoint.set_path("/");
         endpoint.set_query(Some(&format!("{method}&link={encoded}")));
 
         let client = Client::builder().timeout(Duration::from_secs(5)).build()?;
+        // the endpoint contains the decryption key, keep it out of errors and logs
         let resp_text = client
-            .get(endpoint.clone())
-            .send()?
-            .error_for_status()?
-            .text()?;
+            .get(endpoint)
+            .send()
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.text())
+            .map_err(|e| e.without_url())?;
 
         let text = resp_text.trim();
 
-        log::debug!("shortener ({}) GET: {}", method, endpoint);
-        log::debug!("shortener ({}) raw response: {}", method, text);
+        log::debug!("shortener ({}) response: {} bytes", method, text.len());
 
         // JSON first (some proxies return JSON)
         if let Ok(v) = serde_json::from_str::<Value>(text) {
@@ -160,12 +478,47 @@ fn shorten_via_privatebin(opts: &Opts, long_url: &str) -> PbResult<String> {
         .unwrap_or_else(|| Err(PasteError::InvalidData))
 }
 
+/// Apply --redact to the text of a paste or comment.
+/// Detection runs locally, before anything is encrypted or sent.
+/// Returns None after --redact-dry-run, which only reports the findings.
+fn redact_body(opts: &Opts, mut text: String) -> PbResult<Option<String>> {
+    if opts.redact.is_none() && !opts.redact_dry_run {
+        return Ok(Some(text));
+    }
+    let redactor = redact::Redactor::new(&opts.redact_rule)?;
+    let findings = redactor.scan(&text);
+    if opts.redact_dry_run {
+        if findings.is_empty() {
+            eprintln!("nothing to redact");
+        }
+        std::io::stdout().write_all(redact::describe(&text, &findings).as_bytes())?;
+        text.zeroize();
+        return Ok(None);
+    }
+    match opts.redact {
+        Some(mode) => redact::apply(text, &findings, mode).map(Some),
+        None => Ok(Some(text)),
+    }
+}
+
 fn handle_post(opts: &Opts) -> PbResult<()> {
-    let url = opts.get_url();
-    let stdin = get_stdin()?;
-    let api = API::new(url.clone(), opts.clone());
+    let template = opts.resolved_template()?.unwrap_or_default();
+    let qr_file_type = match &opts.qr_file {
+        Some(path) if path.exists() && !opts.overwrite => return Err(PasteError::FileExists),
+        Some(path) => Some(qr::QrFileType::from_path(path)?),
+        None => None,
+    };
+    if opts.upload.is_some() && (opts.redact.is_some() || opts.redact_dry_run) {
+        eprintln!("note: attachments are not scanned for sensitive content");
+    }
+    // an attachment alone makes a paste, so only ask for text without one
+    let Some(stdin) = redact_body(opts, get_body(opts, "paste", opts.upload.is_none())?)? else {
+        return Ok(());
+    };
+    let progress = TerminalProgress::for_stderr();
+    let api = create_api(opts, &progress)?;
 
-    let password = &opts.password.clone().unwrap_or_default();
+    let password = opts.password.clone().unwrap_or_default();
 
     let mut paste = DecryptedPaste {
         paste: stdin,
@@ -180,11 +533,11 @@ fn handle_post(opts: &Opts) -> PbResult<()> {
 
         let mut handle = std::fs::File::open(path)?;
         let metadata = handle.metadata()?;
-        check_filesize(metadata.len(), opts.size_limit);
+        check_filesize(metadata.len(), opts.size_limit, Some(&SizeLimitPrompt))?;
 
-        let mut data = Vec::new();
+        let mut data = Zeroizing::new(Vec::new());
         handle.read_to_end(&mut data)?;
-        let b64_data = base64::encode(data);
+        let b64_data = base64::encode(&data);
 
         paste.attachment = Some(create_dataurl(path.as_os_str(), b64_data));
         paste.attachment_name = Some(
@@ -195,7 +548,13 @@ fn handle_post(opts: &Opts) -> PbResult<()> {
         );
     }
 
-    let res = api.post_paste(&paste, password, opts)?;
+    let res = api.post_paste(&paste, password.expose_secret(), &opts.paste_options());
+    let attachment_name = paste.attachment_name.clone();
+    paste.zeroize();
+    if let Some(progress) = &progress {
+        progress.finish();
+    }
+    let res = res?;
     let long_url = res.to_paste_url().to_string();
 
     let should_shorten = opts.shorten && !opts.no_shorten;
@@ -218,14 +577,44 @@ fn handle_post(opts: &Opts) -> PbResult<()> {
         None
     };
 
-    if opts.json {
+    let qr_url = short_url.as_deref().unwrap_or(&long_url);
+    if opts.qr || opts.qr_file.is_some() {
+        eprintln!("Warning: the qr code contains the decryption key, anyone who scans it can read the paste.");
+    }
+    if opts.qr {
+        eprintln!("{}", qr::render_unicode(qr_url)?);
+    }
+    if let (Some(path), Some(file_type)) = (&opts.qr_file, &qr_file_type) {
+        write_atomically(
+            path,
+            &qr::render_file(qr_url, file_type)?,
+            opts.overwrite,
+            false,
+        )?;
+    }
+
+    let output_format = opts.output_format();
+    if output_format != OutputFormat::Text {
         let mut output: Value = serde_json::to_value(res.clone())?;
         output["pasteurl"] = Value::String(long_url.clone());
         output["deleteurl"] = Value::String(res.to_delete_url().to_string());
         if let Some(s) = &short_url {
             output["shorturl"] = Value::String(s.clone());
         }
-        std::io::stdout().write_all(serde_json::to_string_pretty(&output)?.as_bytes())?;
+        let rendered = match output_format {
+            OutputFormat::Yaml => serde_yaml::to_string(&output)?,
+            OutputFormat::Template => {
+                // {shorturl} falls back to the paste url so templates don't need to care
+                if short_url.is_none() {
+                    output["shorturl"] = Value::String(long_url.clone());
+                }
+                output["expire"] = Value::String(opts.expire.clone());
+                output["attachment_name"] = attachment_name.map(Value::String).unwrap_or_default();
+                template::render(&template, &output)
+            }
+            _ => serde_json::to_string_pretty(&output)?,
+        };
+        std::io::stdout().write_all(rendered.as_bytes())?;
     } else {
         let to_print = short_url.as_deref().unwrap_or(&long_url);
         std::io::stdout().write_all(to_print.as_bytes())?;
@@ -236,37 +625,366 @@ fn handle_post(opts: &Opts) -> PbResult<()> {
 }
 
 fn handle_comment(opts: &Opts) -> PbResult<()> {
-    let url = opts.get_url();
-    let paste_id = url.query().unwrap();
-    let fragment = url.fragment().ok_or(PasteError::MissingDecryptionKey)?;
-    // '-' character may be found at start of fragment. This should be stripped.
-    // It is used to activate "warn before read" feature for burn on read pastes.
-    let key = fragment.strip_prefix('-').unwrap_or(fragment);
+    let (paste_id, key, _) = parse_paste_url(opts.get_url()?)?;
 
-    let stdin = get_stdin()?;
-    let api = API::new(url.clone(), opts.clone());
+    let Some(stdin) = redact_body(opts, get_body(opts, "comment", true)?)? else {
+        return Ok(());
+    };
+    let progress = TerminalProgress::for_stderr();
+    let api = create_api(opts, &progress)?;
     let content = DecryptedComment {
         comment: stdin,
         nickname: opts.comment_as.clone(),
     };
     let parent_id = &opts.comment_to.clone().unwrap_or(paste_id.into());
-    let password = &opts.password.clone().unwrap_or_default();
+    let password = opts.password.clone().unwrap_or_default();
 
-    api.post_comment(&content, paste_id, parent_id, key, password, opts)?;
+    let res = api.post_comment(
+        &content,
+        paste_id,
+        parent_id,
+        key,
+        password.expose_secret(),
+        &opts.comment_options(),
+    );
+    if let Some(progress) = &progress {
+        progress.finish();
+    }
+    res?;
 
     Ok(())
 }
 
+const DEFAULT_WATCH_INTERVAL: u64 = 30;
+const DEFAULT_WATCH_MAX_INTERVAL: u64 = 600;
+
+fn handle_watch(opts: &Opts) -> PbResult<()> {
+    let (paste_id, key, warn_before_read) = parse_paste_url(opts.get_url()?)?;
+    if warn_before_read {
+        return Err(PasteError::WatchBurnAfterReading);
+    }
+    // the burn flag is only known after fetching, which already destroys the paste
+    confirm_destructive_read(
+        opts,
+        "Watching deletes this paste if it is burn after reading. Continue?",
+        PasteError::WatchMayBurn,
+    )?;
+    // no progress display, it would interleave with the comments
+    let api = create_api(opts, &None)?;
+    let paste = api.get_paste(paste_id)?;
+    if paste.adata.burn == 1 {
+        eprintln!("note: paste is burn after reading and has been deleted from the server");
+        return Err(PasteError::WatchBurnAfterReading);
+    }
+    if paste.adata.discuss == 0 {
+        return Err(PasteError::WatchNoDiscussion);
+    }
+    let (mut content, password) = decrypt_paste(opts, &paste, key)?;
+    let expires_at = paste.metadata(&content).expires_at;
+    content.zeroize();
+
+    let mut seen: std::collections::HashSet<String> = paste
+        .comments
+        .iter()
+        .flatten()
+        .map(|c| c.id.clone())
+        .collect();
+    eprintln!(
+        "watching for new comments, {} so far. Press ctrl-c to stop",
+        seen.len()
+    );
+
+    let interval = Duration::from_secs(opts.watch_interval.unwrap_or(DEFAULT_WATCH_INTERVAL));
+    let max_interval = Duration::from_secs(
+        opts.watch_max_interval
+            .unwrap_or(DEFAULT_WATCH_MAX_INTERVAL)
+            .max(interval.as_secs()),
+    );
+    let mut wait = interval;
+    let color =
+        std::io::stdout().is_terminal() && !opts.no_color && std::env::var_os("NO_COLOR").is_none();
+
+    loop {
+        let now = std::time::SystemTime::now();
+        if let Some(expires_at) = expires_at.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)) {
+            match expires_at.duration_since(now) {
+                Ok(left) => std::thread::sleep(wait.min(left)),
+                Err(_) => {
+                    eprintln!("the paste has expired");
+                    return Ok(());
+                }
+            }
+        } else {
+            std::thread::sleep(wait);
+        }
+
+        let paste = match api.get_paste(paste_id) {
+            Ok(paste) => {
+                wait = interval;
+                paste
+            }
+            Err(PasteError::PasteNotFound(_)) => {
+                eprintln!("the paste has been deleted or has expired");
+                return Ok(());
+            }
+            Err(err @ (PasteError::Request(_) | PasteError::UnexpectedResponse { .. })) => {
+                wait = (wait * 2).min(max_interval);
+                log::warn!("polling failed, retrying in {}s: {err}", wait.as_secs());
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        let mut new_comments: Vec<_> = paste
+            .comments
+            .iter()
+            .flatten()
+            .filter(|comment| !seen.contains(&comment.id))
+            .collect();
+        new_comments.sort_by_key(|comment| comment.meta.created);
+
+        for comment in new_comments {
+            let mut node = comment.to_node_with_limits(
+                key,
+                password.expose_secret(),
+                opts.decryption_limits(),
+            );
+            let reply_to = Some(node.parent_id.as_str()).filter(|parent| *parent != paste_id);
+            match &opts.on_comment {
+                Some(command) => run_comment_hook(command, &node),
+                None if opts.output_format() == OutputFormat::Json => {
+                    writeln!(std::io::stdout(), "{}", serde_json::to_string(&node)?)?;
+                }
+                None => {
+                    let rendered = comments::render_comment(&node, reply_to, 0, color);
+                    writeln!(std::io::stdout(), "{rendered}")?;
+                }
+            }
+            node.zeroize();
+            seen.insert(comment.id.clone());
+        }
+    }
+}
+
+/// Run the --on-comment hook with the comment text on stdin and its details in the environment.
+/// A failing hook is reported but does not stop watching.
+fn run_comment_hook(command: &str, comment: &CommentNode) {
+    if let Some(error) = &comment.decrypt_error {
+        log::warn!("not running the comment hook for {}: {error}", comment.id);
+        return;
+    }
+    let result = shell_command(command)
+        .env("PBCLI_COMMENT_ID", &comment.id)
+        .env("PBCLI_COMMENT_PARENT", &comment.parent_id)
+        .env(
+            "PBCLI_COMMENT_NICKNAME",
+            comment.nickname.as_deref().unwrap_or_default(),
+        )
+        .env(
+            "PBCLI_COMMENT_CREATED",
+            comment.created.unwrap_or_default().to_string(),
+        )
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                let text = comment.comment.as_deref().unwrap_or_default();
+                match stdin.write_all(text.as_bytes()) {
+                    Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => {}
+                    result => result?,
+                }
+            }
+            child.wait()
+        });
+    match result {
+        Ok(status) if status.success() => {}
+        Ok(status) => log::warn!("comment hook exited with {status}"),
+        Err(err) => log::warn!("failed to run comment hook: {err}"),
+    }
+}
+
+fn handle_verify(opts: &Opts) -> PbResult<()> {
+    let (paste_id, key, warn_before_read) = parse_paste_url(opts.get_url()?)?;
+    let template = opts.resolved_template()?.unwrap_or_default();
+    if warn_before_read {
+        confirm_burn_after_reading(opts)?;
+    } else {
+        // the burn flag is only known after fetching, which already destroys the paste
+        confirm_destructive_read(
+            opts,
+            "Verifying deletes this paste if it is burn after reading. Continue?",
+            PasteError::VerifyMayBurn,
+        )?;
+    }
+
+    let paste = fetch_paste(opts, paste_id)?;
+    if paste.adata.burn == 1 {
+        // also without a terminal, scripts pass --yes for every paste
+        eprintln!("note: paste is burn after reading and has been deleted from the server");
+    }
+    let (mut content, password) = decrypt_paste(opts, &paste, key)?;
+    let mut comments = comment_tree(opts, &paste, key, &password)?;
+    let metadata = paste.metadata(&content);
+    content.zeroize();
+    comments.zeroize();
+
+    match opts.output_format() {
+        OutputFormat::Json => {
+            let output = serde_json::to_string_pretty(&metadata_value(&metadata)?)?;
+            std::io::stdout().write_all(output.as_bytes())?;
+            writeln!(std::io::stdout())?;
+            return Ok(());
+        }
+        OutputFormat::Yaml => {
+            let output = serde_yaml::to_string(&metadata_value(&metadata)?)?;
+            std::io::stdout().write_all(output.as_bytes())?;
+            return Ok(());
+        }
+        OutputFormat::Template => {
+            let output = template::render(&template, &metadata_value(&metadata)?);
+            std::io::stdout().write_all(output.as_bytes())?;
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    let mut out = std::io::stdout();
+    writeln!(out, "id:             {}", metadata.id)?;
+    writeln!(out, "format:         {}", format_name(metadata.format))?;
+    writeln!(out, "burn:           {}", yes_no(metadata.burn))?;
+    writeln!(out, "discussion:     {}", yes_no(metadata.discussion))?;
+    if let Some(expire) = &metadata.expire {
+        writeln!(out, "expire:         {}", expire)?;
+    }
+    if let Some(ttl) = metadata.time_to_live {
+        writeln!(out, "time to live:   {}s", ttl)?;
+    }
+    writeln!(out, "comments:       {}", metadata.comment_count)?;
+    if let Some(name) = &metadata.attachment_name {
+        writeln!(
+            out,
+            "attachment:     {} ({}, {} bytes)",
+            name,
+            metadata.attachment_mime.as_deref().unwrap_or("unknown"),
+            metadata.attachment_size.unwrap_or_default()
+        )?;
+    }
+    writeln!(out, "plaintext size: {} bytes", metadata.plaintext_size)?;
+    Ok(())
+}
+
+/// Metadata as json, with expiry additionally formatted for humans
+fn metadata_value(metadata: &PasteMetadata) -> PbResult<Value> {
+    let mut value = serde_json::to_value(metadata)?;
+    value["expires_in"] = Value::String(format_expires_in(metadata));
+    Ok(value)
+}
+
+fn format_expires_in(metadata: &PasteMetadata) -> String {
+    match metadata.time_to_live {
+        None => "never".to_string(),
+        Some(ttl) if ttl <= 0 => "expired".to_string(),
+        Some(ttl) => {
+            let secs = ttl as u64;
+            // minute precision is plenty for anything longer than a minute
+            let secs = if secs >= 60 { secs - secs % 60 } else { secs };
+            format!(
+                "in {}",
+                humantime::format_duration(Duration::from_secs(secs))
+            )
+        }
+    }
+}
+
+fn format_metadata_header(metadata: &PasteMetadata) -> String {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    let expires = match metadata.expires_at {
+        Some(expires_at) => format!(
+            "{} ({})",
+            humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(expires_at)),
+            format_expires_in(metadata)
+        ),
+        None => format_expires_in(metadata),
+    };
+    format!(
+        "format: {} | burn: {} | discussion: {} | expires: {} | comments: {}",
+        format_name(metadata.format),
+        yes_no(metadata.burn),
+        yes_no(metadata.discussion),
+        expires,
+        metadata.comment_count
+    )
+}
+
+fn format_name(format: PasteFormat) -> String {
+    format
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
 fn handle_scrape(opts: &Opts) -> PbResult<()> {
-    let url = opts.get_url();
-    let api = API::new(url.clone(), opts.clone());
+    let url = opts.get_url()?;
+    let api = API::new(url.clone(), opts.client_config()?);
     let expiries = api.scrape_expiries()?;
     std::io::stdout().write_all(format!("{:?}", expiries).as_bytes())?;
     writeln!(std::io::stdout())?;
     Ok(())
 }
 
+fn handle_capabilities(opts: &Opts) -> PbResult<()> {
+    let url = opts.get_url()?;
+    let api = API::new(url.clone(), opts.client_config()?);
+    let capabilities = if opts.refresh_capabilities {
+        api.refresh_capabilities()?
+    } else {
+        api.capabilities()?
+    };
+    std::io::stdout().write_all(serde_json::to_string_pretty(&capabilities)?.as_bytes())?;
+    writeln!(std::io::stdout())?;
+    Ok(())
+}
+
+fn handle_capabilities_cache(opts: &Opts) -> PbResult<()> {
+    let cache = match CapabilityCache::default_path() {
+        Some(path) if !opts.no_capabilities_cache => CapabilityCache::load(&path),
+        _ => CapabilityCache::default(),
+    };
+    std::io::stdout().write_all(serde_json::to_string_pretty(cache.entries())?.as_bytes())?;
+    writeln!(std::io::stdout())?;
+    Ok(())
+}
+
+/// The option which gets past an error, the library messages do not know about options
+fn option_hint(err: &PasteError) -> Option<&'static str> {
+    match err {
+        PasteError::EmptyBody(_) => Some("use --allow-empty to post it anyway"),
+        PasteError::SensitiveContent(_) => {
+            Some("use --redact-dry-run to list them or --redact mask to mask them")
+        }
+        PasteError::KdfIterationsExceeded { .. } => Some("use --max-kdf-iterations to allow it"),
+        PasteError::DecompressedSizeExceeded { .. } => {
+            Some("use --max-decompressed-size to allow it")
+        }
+        PasteError::BurnAfterReading => Some("use --yes to open it anyway"),
+        PasteError::VerifyMayBurn => Some("use --yes to verify it anyway"),
+        PasteError::WatchMayBurn => Some("use --yes to watch it anyway"),
+        _ => None,
+    }
+}
+
 fn main() -> PbResult<()> {
+    let result = run();
+    if let Err(err) = &result {
+        if let Some(hint) = option_hint(err) {
+            eprintln!("{err}, {hint}");
+        }
+    }
+    result
+}
+
+fn run() -> PbResult<()> {
     crate::logger::SimpleLogger::init()?;
 
     if pbcli::config::has_debug_flag() {
@@ -282,16 +1000,31 @@ fn main() -> PbResult<()> {
 
     let opts: Opts = Opts::parse_from(&merged_args);
 
+    // the only mode which does not need a url
+    if opts.capabilities_cache {
+        return handle_capabilities_cache(&opts);
+    }
+
     if opts.scrape_expiries {
         return handle_scrape(&opts);
     }
 
-    let url_has_query = opts.get_url().query().is_some();
+    if opts.capabilities || opts.refresh_capabilities {
+        return handle_capabilities(&opts);
+    }
+
+    let url_has_query = opts.get_url()?.query().is_some();
     if url_has_query {
+        if opts.verify {
+            return handle_verify(&opts);
+        }
+        if opts.watch {
+            return handle_watch(&opts);
+        }
         if opts.comment {
             handle_comment(&opts)?;
             // show paste with comments after commenting
-            if opts.json {
+            if opts.output_format() != OutputFormat::Text {
                 handle_get(&opts)?;
             }
             return Ok(());
@@ -303,3 +1036,184 @@ fn main() -> PbResult<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Empty directory which is removed when dropped
+    pub(crate) struct TestDir(pub(crate) PathBuf);
+
+    impl TestDir {
+        pub(crate) fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("pbcli-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir(&path).unwrap();
+            Self(path)
+        }
+
+        pub(crate) fn file_names(&self) -> Vec<String> {
+            let mut names: Vec<_> = std::fs::read_dir(&self.0)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+    use pbcli::privatebin::Comment;
+
+    fn paste_with_broken_comment() -> Paste {
+        Paste {
+            id: "paste".into(),
+            comments: Some(vec![Comment {
+                id: "broken".into(),
+                parentid: "paste".into(),
+                ct: "bm90IGEgY2lwaGVydGV4dA==".into(),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }
+    }
+
+    fn body_sources_opts(dir: &TestDir, extra: &[&str]) -> Opts {
+        let a = dir.0.join("a.txt");
+        let b = dir.0.join("b.txt");
+        std::fs::write(&a, "first").unwrap();
+        std::fs::write(&b, "second\n").unwrap();
+        let mut args = vec![
+            "pbcli",
+            "https://example.com/",
+            "--from-file",
+            b.to_str().unwrap(),
+            "--from-file",
+            a.to_str().unwrap(),
+        ];
+        args.extend(extra);
+        Opts::parse_from(args)
+    }
+
+    #[test]
+    fn body_sources_keep_their_order() {
+        let dir = TestDir::new("body-order");
+        let opts = body_sources_opts(&dir, &[]);
+        assert_eq!(read_body_sources(&opts).unwrap(), "second\nfirst");
+    }
+
+    #[test]
+    fn body_sources_with_headers() {
+        let dir = TestDir::new("body-headers");
+        let opts = body_sources_opts(&dir, &["--file-headers"]);
+        assert_eq!(
+            read_body_sources(&opts).unwrap(),
+            format!(
+                "==> {} <==\nsecond\n\n==> {} <==\nfirst\n",
+                dir.0.join("b.txt").display(),
+                dir.0.join("a.txt").display()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn command_output_follows_the_files() {
+        let dir = TestDir::new("body-command");
+        let opts = body_sources_opts(&dir, &["--from-command", "echo third", "--file-headers"]);
+        let body = read_body_sources(&opts).unwrap();
+        assert!(body.ends_with("first\n\n==> $ echo third <==\nthird\n"));
+
+        let opts = body_sources_opts(&dir, &["--from-command", "exit 3"]);
+        assert!(matches!(
+            read_body_sources(&opts),
+            Err(PasteError::CommandFailed(_))
+        ));
+    }
+
+    #[test]
+    fn missing_body_files_fail() {
+        let dir = TestDir::new("body-missing");
+        let missing = dir.0.join("missing.txt");
+        let opts = Opts::parse_from([
+            "pbcli",
+            "https://example.com/",
+            "--from-file",
+            missing.to_str().unwrap(),
+        ]);
+        assert!(matches!(read_body_sources(&opts), Err(PasteError::Io(_))));
+    }
+
+    #[test]
+    fn option_hints() {
+        let err = PasteError::KdfIterationsExceeded {
+            iterations: 2_000_000,
+            limit: 1_000_000,
+        };
+        assert!(!err.to_string().contains("--"));
+        assert_eq!(
+            option_hint(&err),
+            Some("use --max-kdf-iterations to allow it")
+        );
+        assert_eq!(
+            option_hint(&PasteError::BurnAfterReading),
+            Some("use --yes to open it anyway")
+        );
+        assert_eq!(option_hint(&PasteError::MissingPasteId), None);
+    }
+
+    #[test]
+    fn comment_tree_keeps_failed_comments() {
+        let opts = Opts::parse_from(["pbcli", "https://example.com/"]);
+        let key = bs58::encode([1u8; 32]).into_string();
+        let tree = comment_tree(
+            &opts,
+            &paste_with_broken_comment(),
+            &key,
+            &SecretString::default(),
+        )
+        .unwrap();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].id, "broken");
+        assert!(tree[0].comment.is_none());
+        assert!(tree[0].decrypt_error.is_some());
+    }
+
+    #[test]
+    fn comment_tree_strict_fails() {
+        let opts = Opts::parse_from(["pbcli", "https://example.com/", "--strict-comments"]);
+        let key = bs58::encode([1u8; 32]).into_string();
+        let result = comment_tree(
+            &opts,
+            &paste_with_broken_comment(),
+            &key,
+            &SecretString::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(PasteError::CommentDecryption { id, .. }) if id == "broken"
+        ));
+    }
+
+    #[test]
+    fn comments_json_keeps_the_previous_shape() {
+        let mut failed = CommentNode::sample("b", "a", vec![]);
+        failed.comment = None;
+        failed.decrypt_error = Some("failed".into());
+        let tree = vec![CommentNode::sample("a", "paste", vec![failed])];
+        assert_eq!(
+            comments_json(&tree),
+            serde_json::json!([{
+                "id": "a",
+                "comment": "text of a",
+                "nickname": null,
+                "replies": [{ "id": "b", "comment": null, "nickname": null, "replies": [] }],
+            }])
+        );
+    }
+}
@@ -1,6 +1,14 @@
-use crate::PasteFormat;
+use crate::client::{ClientConfig, CommentOptions, OidcAuth, PasteOptions};
+use crate::crypto::DecryptionLimits;
+use crate::error::{PasteError, PbResult};
+use crate::privatebin::{
+    CompressionType, PasteFormat, DEFAULT_COMPRESSION_LEVEL, MAX_COMPRESSION_LEVEL,
+    MAX_KDF_ITERATIONS, MIN_KDF_ITERATIONS,
+};
+use crate::secret::SecretString;
 use clap::Parser;
 use parse_size::parse_size;
+use std::time::Duration;
 use url::Url;
 
 const ABOUT: &str = "pbcli is a command line client which allows to upload and download
@@ -8,6 +16,34 @@ pastes from privatebin directly from the command line.
 
 Project home page: https://github.com/Mydayyy/pbcli";
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Yaml,
+    Template,
+}
+
+/// Format of a new paste, or auto to detect it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum FormatOption {
+    Plaintext,
+    Syntax,
+    Markdown,
+    Auto,
+}
+
+/// What to do when the paste text contains sensitive values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum RedactMode {
+    Mask,
+    Abort,
+    Prompt,
+}
+
 #[derive(Debug, Parser, Clone)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 #[clap( version = env ! ("CARGO_PKG_VERSION"), author = "Mydayyy <dev@mydayyy.eu>", about = ABOUT)]
@@ -15,7 +51,7 @@ Project home page: https://github.com/Mydayyy/pbcli";
 #[clap(rename_all = "kebab-case")]
 #[command(args_override_self = true)]
 pub struct Opts {
-    #[clap(required_unless_present("host"))]
+    #[clap(required_unless_present_any(["host", "capabilities_cache"]))]
     pub url: Option<Url>,
 
     #[cfg_attr(feature = "uniffi", uniffi(default = None))]
@@ -23,7 +59,8 @@ pub struct Opts {
     pub host: Option<Url>,
 
     #[clap(long, short = 'f', value_enum, default_value = "plaintext")]
-    pub format: PasteFormat,
+    #[clap(help("auto detects markdown or source code from the --from-file name or the text"))]
+    pub format: FormatOption,
 
     #[cfg_attr(feature = "uniffi", uniffi(default = "1week"))]
     #[clap(long, short = 'e', default_value = "1week")]
@@ -37,9 +74,86 @@ pub struct Opts {
     ))]
     pub size_limit: Option<u64>,
 
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_name = "ITERATIONS")]
+    #[arg(value_parser = clap::value_parser!(u32).range(MIN_KDF_ITERATIONS as i64..=MAX_KDF_ITERATIONS as i64))]
+    #[clap(help("kdf iterations for new pastes and comments (default: 100000)"))]
+    pub kdf_iterations: Option<u32>,
+
+    #[clap(long, value_enum, default_value = "zlib")]
+    #[clap(help("compression for new pastes and comments"))]
+    pub compression: CompressionType,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = 10))]
+    #[clap(long, value_name = "LEVEL", default_value_t = DEFAULT_COMPRESSION_LEVEL)]
+    #[arg(value_parser = clap::value_parser!(u8).range(0..=MAX_COMPRESSION_LEVEL as i64))]
+    #[clap(help("zlib compression level for new pastes and comments"))]
+    pub compression_level: u8,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_name = "SIZE")]
+    #[arg(value_parser = |x: &str| parse_size(x))]
+    #[clap(help("refuse pastes which decompress to more than the given size (default: 100MiB)"))]
+    pub max_decompressed_size: Option<u64>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_name = "ITERATIONS")]
+    #[clap(help("refuse pastes which require more kdf iterations (default: 1000000)"))]
+    pub max_kdf_iterations: Option<u32>,
+
     #[cfg_attr(feature = "uniffi", uniffi(default = false))]
     #[clap(long, help("richer output: for delete_url, comments, etc"))]
     pub json: bool,
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        requires_if("template", "template")
+    )]
+    #[clap(help("output format, template requires --template (default: text)"))]
+    pub output: Option<OutputFormat>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_name = "TEMPLATE")]
+    #[clap(help(
+        "render output through a template with {placeholder} fields, @name uses a preset"
+    ))]
+    pub template: Option<String>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = []))]
+    #[clap(long, value_name = "NAME=TEMPLATE")]
+    #[arg(value_parser = parse_named_value)]
+    #[clap(help("define a named template preset, usually set in the config file"))]
+    pub template_preset: Vec<String>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("print paste metadata to stderr before the content"))]
+    pub header: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("fail if any comment can not be decrypted instead of skipping it"))]
+    pub strict_comments: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("show comments below the paste, also when not printing to a terminal"))]
+    pub show_comments: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("do not color comments, also set by the NO_COLOR environment variable"))]
+    pub no_color: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help(
+        "highlight syntax and render markdown when printing to a terminal, paged through $PAGER"
+    ))]
+    pub pretty: bool,
+
     #[cfg_attr(feature = "uniffi", uniffi(default = false))]
     #[clap(long, short = 'b', conflicts_with = "discussion")]
     #[clap(overrides_with = "no_burn")]
@@ -82,21 +196,109 @@ pub struct Opts {
     #[clap(help("reply to this parent comment"))]
     pub comment_to: Option<String>,
 
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long, requires("url"), conflicts_with = "comment")]
+    #[clap(help("check that the paste decrypts and print its metadata instead of its content"))]
+    pub verify: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long, requires("url"), conflicts_with_all = ["comment", "verify"])]
+    #[clap(help("keep polling the paste and print new comments as they arrive"))]
+    pub watch: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_name = "SECONDS", requires("watch"))]
+    #[arg(value_parser = clap::value_parser!(u64).range(1..))]
+    #[clap(help("seconds between polls while watching (default: 30)"))]
+    pub watch_interval: Option<u64>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_name = "SECONDS", requires("watch"))]
+    #[clap(help("longest wait between polls when the server can not be reached (default: 600)"))]
+    pub watch_max_interval: Option<u64>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_name = "COMMAND", requires("watch"))]
+    #[clap(help(
+        "run a shell command per new comment instead of printing it, the text is passed on stdin"
+    ))]
+    pub on_comment: Option<String>,
+
     #[cfg_attr(feature = "uniffi", uniffi(default = None))]
     #[clap(long, short = 'o', value_name = "FILE")]
     pub download: Option<std::path::PathBuf>,
     #[cfg_attr(feature = "uniffi", uniffi(default = false))]
     #[clap(long, short = 'w')]
-    #[clap(help("overwrite the file given with --download if it already exists"))]
+    #[clap(help(
+        "overwrite the file given with --download, --save-to or --qr-file if it already exists"
+    ))]
     pub overwrite: bool,
 
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_name = "FILE")]
+    #[clap(help("also save the paste text to the given file, written atomically"))]
+    pub save_to: Option<std::path::PathBuf>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long, short = 'y')]
+    #[clap(help("open burn after reading pastes without asking"))]
+    pub yes: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_enum, value_name = "MODE")]
+    #[clap(help(
+        "look for tokens, keys, emails and ips before posting a paste or comment and mask them, abort or ask"
+    ))]
+    pub redact: Option<RedactMode>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = []))]
+    #[clap(long, value_name = "NAME=REGEX")]
+    #[arg(value_parser = parse_named_value)]
+    #[clap(help("additional pattern to redact, usually set in the config file. Can be repeated"))]
+    pub redact_rule: Vec<String>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("show what would be redacted without posting the paste or comment"))]
+    pub redact_dry_run: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("post pastes and comments even if they are empty"))]
+    pub allow_empty: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("print the url of a new paste as qr code to stderr"))]
+    pub qr: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_name = "FILE")]
+    #[clap(help("write the url of a new paste as qr code to a .png or .svg file"))]
+    pub qr_file: Option<std::path::PathBuf>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = []))]
+    #[clap(long, value_name = "FILE")]
+    #[clap(help("read the paste or comment text from a file, - for stdin. Can be repeated"))]
+    pub from_file: Vec<std::path::PathBuf>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_name = "COMMAND")]
+    #[clap(help("read the paste or comment text from the output of a shell command"))]
+    pub from_command: Option<String>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("put a header with the file name or command before each part of the text"))]
+    pub file_headers: bool,
+
     #[cfg_attr(feature = "uniffi", uniffi(default = None))]
     #[clap(long, short = 'u', value_name = "FILE")]
     pub upload: Option<std::path::PathBuf>,
 
     #[cfg_attr(feature = "uniffi", uniffi(default = None))]
     #[clap(long, short = 'p')]
-    pub password: Option<String>,
+    pub password: Option<SecretString>,
 
     #[cfg_attr(feature = "uniffi", uniffi(default = None))]
     #[clap(long, requires_all(& ["oidc_client_id", "oidc_username", "oidc_password"]))]
@@ -116,7 +318,7 @@ pub struct Opts {
     #[cfg_attr(feature = "uniffi", uniffi(default = None))]
     #[clap(long)]
     #[clap(help("password to send to the token endpoint"))]
-    pub oidc_password: Option<String>,
+    pub oidc_password: Option<SecretString>,
 
     #[cfg_attr(feature = "uniffi", uniffi(default = None))]
     #[clap(long, value_name = "FILE")]
@@ -147,12 +349,238 @@ pub struct Opts {
     #[clap(long)]
     #[clap(help("attempt scraping supported expiries of given host and exit"))]
     pub scrape_expiries: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("query features and limits of given host and exit"))]
+    pub capabilities: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("query features and limits of given host, update the cache and exit"))]
+    pub refresh_capabilities: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("print the cached capabilities of all hosts and exit"))]
+    pub capabilities_cache: bool,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = None))]
+    #[clap(long, value_name = "SECONDS")]
+    #[clap(help("how long cached capabilities are used before querying again (default: 86400)"))]
+    pub capabilities_ttl: Option<u64>,
+
+    #[cfg_attr(feature = "uniffi", uniffi(default = false))]
+    #[clap(long)]
+    #[clap(help("do not read or write the capability cache"))]
+    pub no_capabilities_cache: bool,
 }
 
 impl Opts {
-    pub fn get_url(&self) -> &Url {
+    /// Output format, --json and --template imply their format if --output is not given
+    pub fn output_format(&self) -> OutputFormat {
+        match self.output {
+            Some(output) => output,
+            None if self.template.is_some() => OutputFormat::Template,
+            None if self.json => OutputFormat::Json,
+            None => OutputFormat::Text,
+        }
+    }
+
+    /// The template given with --template, with `@name` resolved against the presets.
+    /// Later presets override earlier ones of the same name.
+    pub fn resolved_template(&self) -> PbResult<Option<String>> {
+        let template = match &self.template {
+            None => return Ok(None),
+            Some(template) => template,
+        };
+        let name = match template.strip_prefix('@') {
+            None => return Ok(Some(template.clone())),
+            Some(name) => name,
+        };
+        self.template_preset
+            .iter()
+            .rev()
+            .filter_map(|preset| preset.split_once('='))
+            .find(|(preset_name, _)| *preset_name == name)
+            .map(|(_, template)| Some(template.to_string()))
+            .ok_or_else(|| PasteError::UnknownTemplatePreset(name.to_string()))
+    }
+
+    /// Connection settings for the API client
+    pub fn client_config(&self) -> PbResult<ClientConfig> {
+        Ok(ClientConfig {
+            timeout: self.timeout.map(Duration::from_secs),
+            insecure: self.insecure,
+            ca_cert: self.ca_cert.clone(),
+            oidc: self.oidc_auth()?,
+            capabilities_cache: !self.no_capabilities_cache,
+            capabilities_ttl: self.capabilities_ttl.map(Duration::from_secs),
+        })
+    }
+
+    /// Oidc credentials if any oidc option is set. Clap requires all of them together,
+    /// callers building `Opts` themselves get the first missing one as an error.
+    fn oidc_auth(&self) -> PbResult<Option<OidcAuth>> {
+        if self.oidc_token_url.is_none()
+            && self.oidc_client_id.is_none()
+            && self.oidc_username.is_none()
+            && self.oidc_password.is_none()
+        {
+            return Ok(None);
+        }
+        fn required<T: Clone>(value: &Option<T>, name: &str) -> PbResult<T> {
+            value
+                .clone()
+                .ok_or_else(|| PasteError::MissingOption(name.to_string()))
+        }
+        Ok(Some(OidcAuth {
+            token_url: required(&self.oidc_token_url, "oidc_token_url")?,
+            client_id: required(&self.oidc_client_id, "oidc_client_id")?,
+            username: required(&self.oidc_username, "oidc_username")?,
+            password: required(&self.oidc_password, "oidc_password")?,
+        }))
+    }
+
+    /// Settings for a new paste
+    pub fn paste_options(&self) -> PasteOptions {
+        // a single text file names the text better than the attachment does
+        let file_name = match self.from_file.as_slice() {
+            [path] if path.as_os_str() != "-" && !self.file_headers => path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string()),
+            _ => None,
+        };
+        PasteOptions {
+            expire: self.expire.clone(),
+            format: match self.format {
+                FormatOption::Plaintext | FormatOption::Auto => PasteFormat::Plaintext,
+                FormatOption::Syntax => PasteFormat::Syntax,
+                FormatOption::Markdown => PasteFormat::Markdown,
+            },
+            auto_format: self.format == FormatOption::Auto,
+            file_name,
+            burn: self.burn,
+            discussion: self.discussion,
+            size_limit: self.size_limit,
+            kdf_iterations: self.kdf_iterations,
+            compression: self.compression,
+            compression_level: self.compression_level,
+        }
+    }
+
+    /// Settings for a new comment
+    pub fn comment_options(&self) -> CommentOptions {
+        CommentOptions {
+            size_limit: self.size_limit,
+            kdf_iterations: self.kdf_iterations,
+            compression: self.compression,
+            compression_level: self.compression_level,
+        }
+    }
+
+    pub fn decryption_limits(&self) -> DecryptionLimits {
+        let defaults = DecryptionLimits::default();
+        DecryptionLimits {
+            max_decompressed_size: self
+                .max_decompressed_size
+                .unwrap_or(defaults.max_decompressed_size),
+            max_kdf_iterations: self
+                .max_kdf_iterations
+                .unwrap_or(defaults.max_kdf_iterations),
+        }
+    }
+
+    /// The url argument, or --host if no url was given
+    pub fn get_url(&self) -> PbResult<&Url> {
         self.url
             .as_ref()
-            .unwrap_or_else(|| self.host.as_ref().unwrap())
+            .or(self.host.as_ref())
+            .ok_or_else(|| PasteError::MissingOption("url".into()))
+    }
+}
+
+/// Accept NAME=VALUE pairs with a non empty name
+fn parse_named_value(value: &str) -> Result<String, String> {
+    match value.split_once('=') {
+        Some((name, _)) if !name.is_empty() => Ok(value.to_string()),
+        _ => Err("expected NAME=VALUE".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capabilities_cache_does_not_need_a_url() {
+        let opts = Opts::parse_from(["pbcli", "--capabilities-cache", "--scrape-expiries"]);
+        assert!(opts.capabilities_cache);
+        assert!(matches!(
+            opts.get_url(),
+            Err(PasteError::MissingOption(name)) if name == "url"
+        ));
+
+        let opts = Opts::parse_from(["pbcli", "--host", "https://example.com/"]);
+        assert_eq!(opts.get_url().unwrap().as_str(), "https://example.com/");
+        assert!(Opts::try_parse_from(["pbcli", "--scrape-expiries"]).is_err());
+    }
+
+    #[test]
+    fn format_auto_detects_the_format() {
+        let options = |args: &[&str]| {
+            Opts::parse_from(["pbcli", "https://example.com/"].iter().chain(args)).paste_options()
+        };
+        let auto = options(&["--format", "auto"]);
+        assert!(auto.auto_format);
+        assert_eq!(auto.format, PasteFormat::Plaintext);
+
+        // a format given later, as on the command line after the config file, wins
+        let markdown = options(&["--format", "auto", "--format", "markdown"]);
+        assert!(!markdown.auto_format);
+        assert_eq!(markdown.format, PasteFormat::Markdown);
+        assert!(!options(&[]).auto_format);
+    }
+
+    #[test]
+    fn redact_can_be_combined_with_upload() {
+        let opts = Opts::parse_from([
+            "pbcli",
+            "https://example.com/",
+            "--redact=mask",
+            "--upload",
+            "main.rs",
+        ]);
+        assert_eq!(opts.redact, Some(RedactMode::Mask));
+        assert!(opts.upload.is_some());
+    }
+
+    #[test]
+    fn client_config_rejects_partial_oidc_settings() {
+        let mut opts = Opts::parse_from(["pbcli", "https://example.com/"]);
+        assert!(opts.client_config().unwrap().oidc.is_none());
+
+        opts.oidc_token_url = Some("https://example.com/token".into());
+        opts.oidc_client_id = Some("pbcli".into());
+        assert!(matches!(
+            opts.client_config(),
+            Err(PasteError::MissingOption(name)) if name == "oidc_username"
+        ));
+
+        opts.oidc_username = Some("user".into());
+        opts.oidc_password = Some(SecretString::new("secret".into()));
+        let oidc = opts.client_config().unwrap().oidc.unwrap();
+        assert_eq!(oidc.username, "user");
+    }
+
+    #[test]
+    fn clap_requires_all_oidc_options() {
+        let result = Opts::try_parse_from([
+            "pbcli",
+            "https://example.com/",
+            "--oidc-token-url",
+            "https://example.com/token",
+        ]);
+        assert!(result.is_err());
     }
 }
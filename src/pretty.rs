@@ -0,0 +1,133 @@
+use pbcli::language::Language;
+use pbcli::{PasteFormat, PbResult};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+const THEME: &str = "base16-ocean.dark";
+
+/// Render a paste for the terminal according to its format.
+/// Plaintext pastes are returned unchanged.
+pub(crate) fn render(text: &str, format: PasteFormat) -> String {
+    match format {
+        PasteFormat::Plaintext => text.to_string(),
+        PasteFormat::Markdown => termimad::term_text(text).to_string(),
+        PasteFormat::Syntax => highlight(text),
+    }
+}
+
+fn highlight(text: &str) -> String {
+    let syntax_set = SyntaxSet::load_defaults_newlines();
+    let theme_set = ThemeSet::load_defaults();
+    let syntax = detect_syntax(&syntax_set, text);
+    log::debug!("highlighting paste as {}", syntax.name);
+
+    let mut highlighter = HighlightLines::new(syntax, &theme_set.themes[THEME]);
+    let mut out = String::with_capacity(text.len() * 2);
+    for line in LinesWithEndings::from(text) {
+        match highlighter.highlight_line(line, &syntax_set) {
+            Ok(ranges) => out.push_str(&as_24_bit_terminal_escaped(&ranges, false)),
+            Err(err) => {
+                log::debug!("highlighting failed: {err}");
+                return text.to_string();
+            }
+        }
+    }
+    out.push_str("\x1b[0m");
+    out
+}
+
+/// Syntax for highlighting, from the first line (shebang, modeline, doctype),
+/// json content or the language keywords shared with paste format detection
+fn detect_syntax<'a>(syntax_set: &'a SyntaxSet, text: &str) -> &'a SyntaxReference {
+    let first_line = text.lines().next().unwrap_or_default();
+    if let Some(syntax) = syntax_set.find_syntax_by_first_line(first_line) {
+        return syntax;
+    }
+
+    let trimmed = text.trim_start();
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(text).is_ok()
+    {
+        if let Some(syntax) = syntax_set.find_syntax_by_name("JSON") {
+            return syntax;
+        }
+    }
+
+    Language::from_content(text)
+        .and_then(|language| syntax_set.find_syntax_by_name(language.name))
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text())
+}
+
+/// Show text through `$PAGER` if it is set, otherwise write it to stdout
+pub(crate) fn page(text: &str) -> PbResult<()> {
+    let pager = std::env::var("PAGER").unwrap_or_default();
+    let mut args = pager.split_whitespace();
+    let program = match args.next() {
+        Some(program) => program,
+        None => {
+            std::io::stdout().write_all(text.as_bytes())?;
+            return Ok(());
+        }
+    };
+
+    let mut command = Command::new(program);
+    command.args(args).stdin(Stdio::piped());
+    // let less pass colors through, unless the user configured it already
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            log::debug!("failed to start pager {program}: {err}");
+            std::io::stdout().write_all(text.as_bytes())?;
+            return Ok(());
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        match stdin.write_all(text.as_bytes()) {
+            // the user quit the pager before reading everything
+            Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => {}
+            result => result?,
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pbcli::language::LANGUAGES;
+
+    #[test]
+    fn languages_detected_by_content_can_be_highlighted() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        for language in LANGUAGES.iter().filter(|l| !l.hints.is_empty()) {
+            assert!(
+                syntax_set.find_syntax_by_name(language.name).is_some(),
+                "no syntax for {}",
+                language.name
+            );
+        }
+    }
+
+    #[test]
+    fn detect_syntax_examples() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let name = |text| detect_syntax(&syntax_set, text).name.as_str();
+        assert_eq!(name("#!/usr/bin/env python3\nprint(1)\n"), "Python");
+        assert_eq!(name("{\"a\": [1, 2]}"), "JSON");
+        assert_eq!(
+            name("fn main() {\n    let mut x = Vec::new();\n}\n"),
+            "Rust"
+        );
+        assert_eq!(name("just some words\n"), "Plain Text");
+    }
+}
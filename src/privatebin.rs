@@ -1,16 +1,24 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::crypto::Decryptable;
-use crate::error::PbResult;
+use crate::crypto::{Decryptable, DecryptionLimits};
+use crate::error::{PasteError, PbResult};
+use crate::language::Language;
+use crate::progress::ProgressListener;
+use data_url::DataUrl;
 use rand_core::{RngCore, SeedableRng};
 use serde::ser::{SerializeTuple, Serializer};
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::json;
 use serde_with::skip_serializing_none;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use url::Url;
+use zeroize::{Zeroize, Zeroizing};
 
-#[derive(Default, Deserialize, Debug, Serialize)]
+#[derive(Default, clap::ValueEnum, Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 pub enum CompressionType {
     #[serde(rename = "none")]
     None,
@@ -20,7 +28,7 @@ pub enum CompressionType {
     Zlib,
 }
 
-#[derive(Default, clap::ValueEnum, Deserialize, Debug, Serialize, Clone, Copy)]
+#[derive(Default, clap::ValueEnum, Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 pub enum PasteFormat {
     #[default]
@@ -34,6 +42,78 @@ pub enum PasteFormat {
     Markdown,
 }
 
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown", "mdown", "mkd", "mkdn"];
+const PLAINTEXT_EXTENSIONS: &[&str] = &["txt", "text", "log", "csv"];
+impl PasteFormat {
+    /// Guess the format of a paste from a file name and its content.
+    /// The extension wins, then a shebang, then line based heuristics and language keywords.
+    pub fn detect(text: &str, file_name: Option<&str>) -> Self {
+        let extension = file_name
+            .and_then(|name| std::path::Path::new(name).extension())
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        if let Some(extension) = extension.as_deref() {
+            if MARKDOWN_EXTENSIONS.contains(&extension) {
+                return PasteFormat::Markdown;
+            }
+            if file_name.and_then(Language::from_file_name).is_some() {
+                return PasteFormat::Syntax;
+            }
+            if PLAINTEXT_EXTENSIONS.contains(&extension) {
+                return PasteFormat::Plaintext;
+            }
+        }
+
+        if text.starts_with("#!") {
+            return PasteFormat::Syntax;
+        }
+
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim_end)
+            .filter(|l| !l.is_empty())
+            .collect();
+        if lines.is_empty() {
+            return PasteFormat::Plaintext;
+        }
+
+        let is_heading = |l: &str| {
+            let hashes = l.len() - l.trim_start_matches('#').len();
+            (1..=6).contains(&hashes) && l[hashes..].starts_with(' ')
+        };
+        let headings = lines.iter().filter(|l| is_heading(l)).count();
+        let fences = lines.iter().filter(|l| l.starts_with("```")).count();
+        let markup = lines
+            .iter()
+            .filter(|l| {
+                l.starts_with("- ")
+                    || l.starts_with("* ")
+                    || l.starts_with("> ")
+                    || l.contains("](")
+                    || l.contains("**")
+            })
+            .count();
+        if (headings > 0 || fences >= 2) && headings + fences / 2 + markup >= 2 {
+            return PasteFormat::Markdown;
+        }
+
+        let code = lines
+            .iter()
+            .filter(|l| {
+                l.ends_with(';')
+                    || l.ends_with('{')
+                    || l.ends_with('}')
+                    || l.ends_with("):")
+                    || l.trim_start().starts_with("//")
+            })
+            .count();
+        if lines.len() >= 2 && code * 3 >= lines.len() || Language::from_content(text).is_some() {
+            return PasteFormat::Syntax;
+        }
+
+        PasteFormat::Plaintext
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Default, Deserialize, Debug, Serialize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Object))]
@@ -113,8 +193,23 @@ pub struct Cipher {
     // test: String,
 }
 
+pub const DEFAULT_KDF_ITERATIONS: u32 = 100_000;
+/// Fewer iterations than the web client uses are not accepted for new pastes
+pub const MIN_KDF_ITERATIONS: u32 = DEFAULT_KDF_ITERATIONS;
+/// More iterations would be refused by the default decryption limits
+pub const MAX_KDF_ITERATIONS: u32 = crate::crypto::DEFAULT_MAX_KDF_ITERATIONS;
+pub const DEFAULT_COMPRESSION_LEVEL: u8 = 10;
+pub const MAX_COMPRESSION_LEVEL: u8 = 10;
+
 impl Default for Cipher {
     fn default() -> Self {
+        Cipher::new(DEFAULT_KDF_ITERATIONS, CompressionType::Zlib)
+    }
+}
+
+impl Cipher {
+    /// Cipher parameters for new content, with random salt and iv
+    pub fn new(kdf_iterations: u32, compression_type: CompressionType) -> Self {
         let mut rng = rand_chacha::ChaCha20Rng::from_entropy();
         let mut kdf_salt = [0u8; 8];
         let mut nonce = [0u8; 16];
@@ -124,17 +219,26 @@ impl Default for Cipher {
         Cipher {
             cipher_iv: base64::encode(nonce),
             kdf_salt: base64::encode(kdf_salt),
-            kdf_iterations: 100000,
+            kdf_iterations,
             kdf_keysize: 256,
             cipher_tag_size: 128,
             cipher_algo: "aes".to_string(),
             cipher_mode: "gcm".to_string(),
-            compression_type: CompressionType::Zlib,
+            compression_type,
         }
     }
-}
 
-impl Cipher {
+    /// Cipher parameters for new content, checked against the ranges the web client accepts
+    pub fn with_settings(kdf_iterations: u32, compression_type: CompressionType) -> PbResult<Self> {
+        if !(MIN_KDF_ITERATIONS..=MAX_KDF_ITERATIONS).contains(&kdf_iterations) {
+            return Err(PasteError::InvalidCipherParameters(format!(
+                "kdf iterations must be between {} and {}",
+                MIN_KDF_ITERATIONS, MAX_KDF_ITERATIONS
+            )));
+        }
+        Ok(Cipher::new(kdf_iterations, compression_type))
+    }
+
     /// get vector of bytes representation
     pub fn vec_cipher_iv(&self) -> PbResult<Vec<u8>> {
         base64::decode(&self.cipher_iv).map_err(|e| e.into())
@@ -146,7 +250,7 @@ impl Cipher {
 }
 
 #[skip_serializing_none]
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, Zeroize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 pub struct DecryptedPaste {
     pub paste: String,
@@ -155,13 +259,70 @@ pub struct DecryptedPaste {
 }
 
 #[skip_serializing_none]
-#[derive(Default, Deserialize, Debug, Serialize)]
+#[derive(Default, Deserialize, Debug, Serialize, Zeroize)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 pub struct DecryptedComment {
     pub comment: String,
     pub nickname: Option<String>,
 }
 
+/// Facts about a paste which can be shown without revealing its content
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct PasteMetadata {
+    pub id: String,
+    pub format: PasteFormat,
+    pub burn: bool,
+    pub discussion: bool,
+    pub created: Option<i32>,
+    pub expire: Option<String>,
+    pub time_to_live: Option<i32>,
+    /// unix timestamp in seconds, derived from time_to_live
+    pub expires_at: Option<u64>,
+    pub comment_count: u64,
+    pub attachment_name: Option<String>,
+    pub attachment_mime: Option<String>,
+    pub attachment_size: Option<u64>,
+    pub plaintext_size: u64,
+}
+
+/// A comment with its replies, as part of the comment tree of a paste
+#[derive(Debug, Clone, Serialize, Zeroize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct CommentNode {
+    pub id: String,
+    pub parent_id: String,
+    pub created: Option<i32>,
+    pub icon: Option<String>,
+    /// None if the comment could not be decrypted
+    pub comment: Option<String>,
+    pub nickname: Option<String>,
+    /// Why the comment could not be decrypted
+    pub decrypt_error: Option<String>,
+    /// Replies, oldest first
+    pub replies: Vec<CommentNode>,
+}
+
+impl CommentNode {
+    /// Decrypted comment with the text "text of <id>", created at the unix epoch.
+    /// Shared by the tests of the library and the command line client, which can not
+    /// see `cfg(test)` items of the library.
+    #[doc(hidden)]
+    pub fn sample(id: &str, parent_id: &str, replies: Vec<CommentNode>) -> Self {
+        Self {
+            id: id.into(),
+            parent_id: parent_id.into(),
+            created: Some(0),
+            icon: None,
+            comment: Some(format!("text of {id}")),
+            nickname: None,
+            decrypt_error: None,
+            replies,
+        }
+    }
+}
+
 /// comment.id -> decrypted_comment
 pub type DecryptedCommentsMap = HashMap<String, DecryptedComment>;
 
@@ -176,7 +337,7 @@ pub struct PostCommentResponse {
     pub url: String,
 }
 
-#[derive(Deserialize, Debug, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 #[cfg_attr(feature = "uniffi", derive(uniffi::Object))]
 pub struct PostPasteResponse {
     pub deletetoken: String,
@@ -187,6 +348,20 @@ pub struct PostPasteResponse {
     pub bs58key: String,
 }
 
+/// Key and delete token are redacted
+impl fmt::Debug for PostPasteResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PostPasteResponse")
+            .field("deletetoken", &"<redacted>")
+            .field("id", &self.id)
+            .field("status", &self.status)
+            .field("url", &self.url)
+            .field("baseurl", &self.baseurl)
+            .field("bs58key", &"<redacted>")
+            .finish()
+    }
+}
+
 #[cfg_attr(feature = "uniffi", uniffi::export)]
 impl PostPasteResponse {
     /// Return full paste url, i.e (base + ?id + #bs58key)
@@ -215,6 +390,44 @@ impl PostPasteResponse {
 
 #[cfg_attr(feature = "uniffi", uniffi::export)]
 impl Paste {
+    /// Metadata of the paste and its decrypted content.
+    /// Mime type and size of an attachment which is not a valid data url are None.
+    pub fn metadata(&self, content: &DecryptedPaste) -> PasteMetadata {
+        let url = content
+            .attachment
+            .as_deref()
+            .and_then(|attachment| DataUrl::process(attachment).ok());
+        let attachment_mime = url.as_ref().map(|url| {
+            let mime = url.mime_type();
+            format!("{}/{}", mime.type_, mime.subtype)
+        });
+        let attachment_size = url
+            .and_then(|url| url.decode_to_vec().ok())
+            .map(|(body, _)| Zeroizing::new(body).len() as u64);
+
+        PasteMetadata {
+            id: self.id.clone(),
+            format: self.adata.format,
+            burn: self.adata.burn == 1,
+            discussion: self.adata.discuss == 1,
+            created: self.meta.created,
+            expire: self.meta.expire.clone(),
+            time_to_live: self.meta.time_to_live,
+            expires_at: self.meta.time_to_live.map(|ttl| {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                now + ttl.max(0) as u64
+            }),
+            comment_count: self.comments.as_ref().map_or(0, |c| c.len() as u64),
+            attachment_name: content.attachment_name.clone(),
+            attachment_mime,
+            attachment_size,
+            plaintext_size: content.paste.len() as u64,
+        }
+    }
+
     pub fn decrypt(&self, bs58_key: &str) -> PbResult<DecryptedPaste> {
         self.decrypt_with_password(bs58_key, "")
     }
@@ -224,8 +437,28 @@ impl Paste {
         bs58_key: &str,
         password: &str,
     ) -> PbResult<DecryptedPaste> {
-        let key = bs58::decode(bs58_key).into_vec()?;
-        crate::crypto::decrypt_with_password(self, &key, password)
+        self.decrypt_with_limits(bs58_key, password, DecryptionLimits::default())
+    }
+
+    pub fn decrypt_with_limits(
+        &self,
+        bs58_key: &str,
+        password: &str,
+        limits: DecryptionLimits,
+    ) -> PbResult<DecryptedPaste> {
+        self.decrypt_with_progress(bs58_key, password, limits, None)
+    }
+
+    /// Same as `decrypt_with_limits`, reporting key derivation, decryption and decompression
+    pub fn decrypt_with_progress(
+        &self,
+        bs58_key: &str,
+        password: &str,
+        limits: DecryptionLimits,
+        listener: Option<Arc<dyn ProgressListener>>,
+    ) -> PbResult<DecryptedPaste> {
+        let key = Zeroizing::new(bs58::decode(bs58_key).into_vec()?);
+        crate::crypto::decrypt_with_progress(self, &key, password, limits, listener.as_deref())
     }
 
     /// Returns a mapping: comment.id -> decrypted_comment
@@ -238,17 +471,60 @@ impl Paste {
         &self,
         bs58_key: &str,
         password: &str,
+    ) -> PbResult<DecryptedCommentsMap> {
+        self.decrypt_comments_with_limits(bs58_key, password, DecryptionLimits::default())
+    }
+
+    /// Returns a mapping: comment.id -> decrypted_comment
+    pub fn decrypt_comments_with_limits(
+        &self,
+        bs58_key: &str,
+        password: &str,
+        limits: DecryptionLimits,
     ) -> PbResult<DecryptedCommentsMap> {
         let mut decrypted_comments = HashMap::new();
         if let Some(comments) = &self.comments {
             for comment in comments {
                 let id = comment.id.clone();
-                decrypted_comments.insert(id, comment.decrypt_with_password(bs58_key, password)?);
+                decrypted_comments
+                    .insert(id, comment.decrypt_with_limits(bs58_key, password, limits)?);
             }
         };
         Ok(decrypted_comments)
     }
 
+    /// Comments as a tree, with top level comments and replies ordered by creation time.
+    /// Comments that fail to decrypt are kept and carry the error instead of their text.
+    pub fn comment_tree(&self, bs58_key: &str, password: &str) -> Vec<CommentNode> {
+        self.comment_tree_with_limits(bs58_key, password, DecryptionLimits::default())
+    }
+
+    /// Like comment_tree, with limits for decryption
+    pub fn comment_tree_with_limits(
+        &self,
+        bs58_key: &str,
+        password: &str,
+        limits: DecryptionLimits,
+    ) -> Vec<CommentNode> {
+        let mut comments: Vec<&Comment> = self.comments.iter().flatten().collect();
+        // stable, so comments created in the same second keep the server order
+        comments.sort_by_key(|c| c.meta.created);
+
+        let mut children: HashMap<&str, Vec<&Comment>> = HashMap::new();
+        for comment in &comments {
+            children.entry(&comment.parentid).or_default().push(comment);
+        }
+
+        let mut visited: HashSet<&str> = HashSet::new();
+        let mut build =
+            |comment| comment_node(comment, &children, &mut visited, bs58_key, password, limits);
+        let roots = children.get(self.id.as_str()).cloned().unwrap_or_default();
+        let mut tree: Vec<CommentNode> = roots.into_iter().filter_map(&mut build).collect();
+        // replies to missing comments or cycles are shown at the top level rather than lost
+        tree.extend(comments.into_iter().filter_map(&mut build));
+        tree
+    }
+
     /// Returns a mapping: comment.id -> [children comment.id]
     pub fn comments_adjacency_map(&self) -> PbResult<CommentsAdjacencyMap> {
         let mut comment_adjacency: CommentsAdjacencyMap = HashMap::new();
@@ -262,7 +538,8 @@ impl Paste {
         Ok(comment_adjacency)
     }
 
-    /// Returns formatted json trees (forest) of decrypted comments
+    /// Returns formatted json trees (forest) of decrypted comments.
+    /// Comments missing from decrypted_comments have a null comment and nickname.
     pub fn comments_formatted_json_trees(
         &self,
         decrypted_comments: &DecryptedCommentsMap,
@@ -283,8 +560,8 @@ impl Paste {
                 .collect();
             json!({
                 "id": id,
-                "comment": decrypted_comments.get(id).unwrap_or(&DecryptedComment::default()).comment,
-                "nickname": decrypted_comments.get(id).unwrap_or(&DecryptedComment::default()).nickname,
+                "comment": decrypted_comments.get(id).map(|c| &c.comment),
+                "nickname": decrypted_comments.get(id).and_then(|c| c.nickname.as_ref()),
                 "replies": formatted_children
             })
         }
@@ -293,6 +570,28 @@ impl Paste {
     }
 }
 
+/// Build the node for comment and its replies, skipping comments already placed in the tree
+fn comment_node<'a>(
+    comment: &'a Comment,
+    children: &HashMap<&str, Vec<&'a Comment>>,
+    visited: &mut HashSet<&'a str>,
+    bs58_key: &str,
+    password: &str,
+    limits: DecryptionLimits,
+) -> Option<CommentNode> {
+    if !visited.insert(&comment.id) {
+        return None;
+    }
+    let mut node = comment.to_node_with_limits(bs58_key, password, limits);
+    node.replies = children
+        .get(comment.id.as_str())
+        .into_iter()
+        .flatten()
+        .filter_map(|c| comment_node(c, children, visited, bs58_key, password, limits))
+        .collect();
+    Some(node)
+}
+
 impl Comment {
     pub fn decrypt(&self, bs58_key: &str) -> PbResult<DecryptedComment> {
         self.decrypt_with_password(bs58_key, "")
@@ -303,8 +602,41 @@ impl Comment {
         bs58_key: &str,
         password: &str,
     ) -> PbResult<DecryptedComment> {
-        let key = bs58::decode(bs58_key).into_vec()?;
-        crate::crypto::decrypt_with_password(self, &key, password)
+        self.decrypt_with_limits(bs58_key, password, DecryptionLimits::default())
+    }
+
+    pub fn decrypt_with_limits(
+        &self,
+        bs58_key: &str,
+        password: &str,
+        limits: DecryptionLimits,
+    ) -> PbResult<DecryptedComment> {
+        let key = Zeroizing::new(bs58::decode(bs58_key).into_vec()?);
+        crate::crypto::decrypt_with_password(self, &key, password, limits)
+    }
+
+    /// Decrypt into a tree node without replies. A failure is recorded in the node.
+    pub fn to_node_with_limits(
+        &self,
+        bs58_key: &str,
+        password: &str,
+        limits: DecryptionLimits,
+    ) -> CommentNode {
+        let (comment, nickname, decrypt_error) =
+            match self.decrypt_with_limits(bs58_key, password, limits) {
+                Ok(decrypted) => (Some(decrypted.comment), decrypted.nickname, None),
+                Err(err) => (None, None, Some(err.to_string())),
+            };
+        CommentNode {
+            id: self.id.clone(),
+            parent_id: self.parentid.clone(),
+            created: self.meta.created,
+            icon: self.meta.icon.clone(),
+            comment,
+            nickname,
+            decrypt_error,
+            replies: vec![],
+        }
     }
 }
 
@@ -343,3 +675,181 @@ impl Serialize for Cipher {
         s.end()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [3u8; 32];
+
+    fn comment(id: &str, parent_id: &str, created: i32, text: &str, key: &[u8]) -> Comment {
+        let mut comment = Comment {
+            id: id.into(),
+            pasteid: "paste".into(),
+            parentid: parent_id.into(),
+            v: 2,
+            meta: Meta {
+                created: Some(created),
+                ..Default::default()
+            },
+            adata: Cipher::new(DEFAULT_KDF_ITERATIONS, CompressionType::Zlib),
+            ..Default::default()
+        };
+        let content = serde_json::to_string(&DecryptedComment {
+            comment: text.into(),
+            nickname: None,
+        })
+        .unwrap();
+        let ct = crate::crypto::encrypt(
+            &content,
+            key,
+            "",
+            &comment.adata,
+            &comment.get_adata_str(),
+            DEFAULT_COMPRESSION_LEVEL,
+            None,
+        )
+        .unwrap();
+        comment.ct = base64::encode(ct);
+        comment
+    }
+
+    /// (depth, id, comment) of each node in display order
+    fn flatten(
+        nodes: &[CommentNode],
+        depth: usize,
+        out: &mut Vec<(usize, String, Option<String>)>,
+    ) {
+        for node in nodes {
+            out.push((depth, node.id.clone(), node.comment.clone()));
+            flatten(&node.replies, depth + 1, out);
+        }
+    }
+
+    #[test]
+    fn comment_tree_orders_and_keeps_every_comment() {
+        let paste = Paste {
+            id: "paste".into(),
+            comments: Some(vec![
+                comment("reply", "root", 20, "reply", &KEY),
+                comment("late-root", "paste", 30, "late root", &KEY),
+                comment("root", "paste", 10, "root", &KEY),
+                comment("orphan", "deleted", 40, "orphan", &KEY),
+                // two comments replying to each other
+                comment("cycle-a", "cycle-b", 50, "cycle a", &KEY),
+                comment("cycle-b", "cycle-a", 60, "cycle b", &KEY),
+                comment("failed", "root", 70, "wrong key", &[9u8; 32]),
+            ]),
+            ..Default::default()
+        };
+        let key = bs58::encode(KEY).into_string();
+        let tree = paste.comment_tree_with_limits(&key, "", DecryptionLimits::default());
+
+        let mut nodes = vec![];
+        flatten(&tree, 0, &mut nodes);
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(
+            nodes,
+            [
+                (0, "root".into(), some("root")),
+                (1, "reply".into(), some("reply")),
+                (1, "failed".into(), None),
+                (0, "late-root".into(), some("late root")),
+                (0, "orphan".into(), some("orphan")),
+                (0, "cycle-a".into(), some("cycle a")),
+                (1, "cycle-b".into(), some("cycle b")),
+            ]
+        );
+
+        let failed = &tree[0].replies[1];
+        assert!(failed.decrypt_error.is_some());
+        assert_eq!(failed.parent_id, "root");
+        assert_eq!(failed.created, Some(70));
+    }
+
+    #[test]
+    fn metadata_of_attachments() {
+        let paste = Paste {
+            id: "paste".into(),
+            ..Default::default()
+        };
+        let content = |attachment: &str| DecryptedPaste {
+            paste: "text".into(),
+            attachment: Some(attachment.into()),
+            attachment_name: Some("file.txt".into()),
+        };
+
+        let metadata = paste.metadata(&content("data:text/plain;base64,aGVsbG8="));
+        assert_eq!(metadata.attachment_mime.as_deref(), Some("text/plain"));
+        assert_eq!(metadata.attachment_size, Some(5));
+        assert_eq!(metadata.plaintext_size, 4);
+
+        // a broken attachment does not fail reading the paste
+        let metadata = paste.metadata(&content("not a data url"));
+        assert_eq!(metadata.attachment_name.as_deref(), Some("file.txt"));
+        assert_eq!(metadata.attachment_mime, None);
+        assert_eq!(metadata.attachment_size, None);
+    }
+
+    #[test]
+    fn detect_format() {
+        let cases: &[(&str, Option<&str>, PasteFormat)] = &[
+            // file name
+            ("anything", Some("notes.md"), PasteFormat::Markdown),
+            ("anything", Some("README.MARKDOWN"), PasteFormat::Markdown),
+            ("anything", Some("main.rs"), PasteFormat::Syntax),
+            ("fn main() {}", Some("build.log"), PasteFormat::Plaintext),
+            // shebang
+            ("#!/bin/sh\nls\n", None, PasteFormat::Syntax),
+            (
+                "#!/usr/bin/env python3\n",
+                Some("script"),
+                PasteFormat::Syntax,
+            ),
+            // markdown
+            (
+                "# Title\n\nSome text with a [link](https://example.com).\n",
+                None,
+                PasteFormat::Markdown,
+            ),
+            (
+                "## Steps\n- first\n- second\n",
+                Some("notes"),
+                PasteFormat::Markdown,
+            ),
+            ("```\ncode\n```\n**bold**\n", None, PasteFormat::Markdown),
+            // code
+            (
+                "int main() {\n    return 0;\n}\n",
+                None,
+                PasteFormat::Syntax,
+            ),
+            (
+                "import os\ndef main():\n    print(os.getcwd())\n",
+                None,
+                PasteFormat::Syntax,
+            ),
+            // plaintext
+            (
+                "Hello,\nsee you tomorrow at 10:00.\nBye\n",
+                None,
+                PasteFormat::Plaintext,
+            ),
+            ("#hashtag without space\n", None, PasteFormat::Plaintext),
+            ("- a single list item\n", None, PasteFormat::Plaintext),
+            ("one line;", None, PasteFormat::Plaintext),
+            // empty
+            ("", None, PasteFormat::Plaintext),
+            ("\n  \n\t\n", None, PasteFormat::Plaintext),
+            ("", Some("empty.rs"), PasteFormat::Syntax),
+        ];
+
+        for (text, file_name, expected) in cases {
+            assert_eq!(
+                PasteFormat::detect(text, *file_name),
+                *expected,
+                "text {text:?}, file name {file_name:?}"
+            );
+        }
+    }
+}
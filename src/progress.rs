@@ -0,0 +1,79 @@
+use std::io::Read;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum ProgressPhase {
+    KeyDerivation,
+    Compression,
+    Encryption,
+    Upload,
+    Download,
+    Decryption,
+    Decompression,
+}
+
+impl ProgressPhase {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProgressPhase::KeyDerivation => "deriving key",
+            ProgressPhase::Compression => "compressing",
+            ProgressPhase::Encryption => "encrypting",
+            ProgressPhase::Upload => "uploading",
+            ProgressPhase::Download => "downloading",
+            ProgressPhase::Decryption => "decrypting",
+            ProgressPhase::Decompression => "decompressing",
+        }
+    }
+}
+
+/// Receives progress updates while a paste is prepared and transferred.
+/// `done` and `total` are byte counts, `total` is None if it is not known upfront.
+#[cfg_attr(feature = "uniffi", uniffi::export(with_foreign))]
+pub trait ProgressListener: Send + Sync {
+    fn on_progress(&self, phase: ProgressPhase, done: u64, total: Option<u64>);
+}
+
+/// Optional listener that can be passed around cheaply
+pub(crate) type Progress<'a> = Option<&'a dyn ProgressListener>;
+
+pub(crate) fn report(progress: Progress, phase: ProgressPhase, done: u64, total: Option<u64>) {
+    if let Some(listener) = progress {
+        listener.on_progress(phase, done, total);
+    }
+}
+
+/// Reader which reports every chunk read from the wrapped reader
+pub(crate) struct ProgressReader<R> {
+    inner: R,
+    listener: Option<Arc<dyn ProgressListener>>,
+    phase: ProgressPhase,
+    done: u64,
+    total: Option<u64>,
+}
+
+impl<R: Read> ProgressReader<R> {
+    pub(crate) fn new(
+        inner: R,
+        listener: Option<Arc<dyn ProgressListener>>,
+        phase: ProgressPhase,
+        total: Option<u64>,
+    ) -> Self {
+        Self {
+            inner,
+            listener,
+            phase,
+            done: 0,
+            total,
+        }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.done += n as u64;
+        report(self.listener.as_deref(), self.phase, self.done, self.total);
+        Ok(n)
+    }
+}
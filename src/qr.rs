@@ -0,0 +1,56 @@
+use pbcli::{PasteError, PbResult};
+use qrcode::render::{svg, unicode};
+use qrcode::QrCode;
+use std::path::Path;
+
+/// File types a qr code can be written as, picked by extension
+pub(crate) enum QrFileType {
+    Png,
+    Svg,
+}
+
+impl QrFileType {
+    pub(crate) fn from_path(path: &Path) -> PbResult<Self> {
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "png" => Ok(QrFileType::Png),
+            "svg" => Ok(QrFileType::Svg),
+            _ => Err(PasteError::UnsupportedQrFileType(extension)),
+        }
+    }
+}
+
+/// Render as unicode half blocks, light on dark so it scans on dark terminals
+pub(crate) fn render_unicode(data: &str) -> PbResult<String> {
+    let code = QrCode::new(data.as_bytes())?;
+    Ok(code
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build())
+}
+
+pub(crate) fn render_file(data: &str, file_type: &QrFileType) -> PbResult<Vec<u8>> {
+    let code = QrCode::new(data.as_bytes())?;
+    match file_type {
+        QrFileType::Svg => Ok(code
+            .render::<svg::Color>()
+            .min_dimensions(256, 256)
+            .build()
+            .into_bytes()),
+        QrFileType::Png => {
+            let image = code
+                .render::<image::Luma<u8>>()
+                .min_dimensions(256, 256)
+                .build();
+            let mut png = std::io::Cursor::new(Vec::new());
+            image
+                .write_to(&mut png, image::ImageFormat::Png)
+                .map_err(|e| PasteError::Io(std::io::Error::other(e)))?;
+            Ok(png.into_inner())
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use serde_json::Value;
+
+/// Replace `{name}` placeholders with the matching top level field of `values`.
+/// Missing fields render as an empty string, `{{` and `}}` produce literal braces
+/// and `\n` / `\t` in the template are turned into newlines and tabs.
+pub(crate) fn render(template: &str, values: &Value) -> String {
+    let template = template.replace("\\n", "\n").replace("\\t", "\t");
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template.as_str();
+
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+
+        match tail
+            .strip_prefix('{')
+            .and_then(|t| t.find('}').map(|end| (t, end)))
+        {
+            Some((t, end)) => {
+                out.push_str(&placeholder_value(values, &t[..end]));
+                rest = &t[end + 1..];
+            }
+            None => {
+                out.push_str(&tail[..1]);
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn placeholder_value(values: &Value, name: &str) -> String {
+    match values.get(name) {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values() -> Value {
+        json!({
+            "id": "f37ca34e72e2ef77",
+            "burn": false,
+            "comment_count": 3,
+            "expire": null,
+            "metadata": { "format": "plaintext" },
+            "tags": ["a", "b"],
+        })
+    }
+
+    #[test]
+    fn replaces_placeholders() {
+        assert_eq!(
+            render("{id}: burn={burn}, {comment_count} comments", &values()),
+            "f37ca34e72e2ef77: burn=false, 3 comments"
+        );
+        assert_eq!(render("no placeholders", &values()), "no placeholders");
+        assert_eq!(render("", &values()), "");
+    }
+
+    #[test]
+    fn missing_and_null_values_are_empty() {
+        assert_eq!(render("[{unknown}]", &values()), "[]");
+        assert_eq!(render("[{expire}]", &values()), "[]");
+        assert_eq!(render("[{}]", &values()), "[]");
+    }
+
+    #[test]
+    fn escaped_braces() {
+        assert_eq!(render("{{id}}", &values()), "{id}");
+        assert_eq!(render("{{{id}}}", &values()), "{f37ca34e72e2ef77}");
+        assert_eq!(render("}}{{", &values()), "}{");
+    }
+
+    #[test]
+    fn unterminated_and_stray_braces_are_kept() {
+        assert_eq!(render("{id", &values()), "{id");
+        assert_eq!(render("a } b", &values()), "a } b");
+        assert_eq!(render("{id} {", &values()), "f37ca34e72e2ef77 {");
+    }
+
+    #[test]
+    fn nested_values_render_as_json() {
+        assert_eq!(render("{metadata}", &values()), r#"{"format":"plaintext"}"#);
+        assert_eq!(render("{tags}", &values()), r#"["a","b"]"#);
+        // only top level fields are placeholders
+        assert_eq!(render("[{metadata.format}]", &values()), "[]");
+        assert_eq!(render("{a{id}}", &values()), "}");
+    }
+
+    #[test]
+    fn escape_sequences() {
+        assert_eq!(render("{id}\\n\\t.", &values()), "f37ca34e72e2ef77\n\t.");
+    }
+}
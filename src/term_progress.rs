@@ -0,0 +1,101 @@
+use pbcli::progress::{ProgressListener, ProgressPhase};
+use std::io::{IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Single line progress indicator drawn on stderr
+pub(crate) struct TerminalProgress {
+    state: Mutex<State>,
+}
+
+struct State {
+    phase: Option<ProgressPhase>,
+    phase_started: Instant,
+    last_draw: Option<Instant>,
+}
+
+impl TerminalProgress {
+    /// Returns a progress indicator if stderr is attached to a terminal
+    pub(crate) fn for_stderr() -> Option<Arc<TerminalProgress>> {
+        if !std::io::stderr().is_terminal() {
+            return None;
+        }
+        Some(Arc::new(TerminalProgress {
+            state: Mutex::new(State {
+                phase: None,
+                phase_started: Instant::now(),
+                last_draw: None,
+            }),
+        }))
+    }
+
+    /// Clears the progress line, if anything was drawn
+    pub(crate) fn finish(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.last_draw.take().is_some() {
+            eprint!("\r\x1b[2K");
+            let _ = std::io::stderr().flush();
+        }
+        state.phase = None;
+    }
+}
+
+impl ProgressListener for TerminalProgress {
+    fn on_progress(&self, phase: ProgressPhase, done: u64, total: Option<u64>) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        if state.phase != Some(phase) {
+            state.phase = Some(phase);
+            state.phase_started = now;
+        } else if total != Some(done)
+            && state
+                .last_draw
+                .is_some_and(|last| now.duration_since(last) < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        state.last_draw = Some(now);
+
+        let line = match phase {
+            ProgressPhase::KeyDerivation => format!("{}...", phase.label()),
+            _ => {
+                let elapsed = now.duration_since(state.phase_started).as_secs_f64();
+                let rate = if elapsed > 0.0 {
+                    format!(" ({}/s)", format_bytes((done as f64 / elapsed) as u64))
+                } else {
+                    String::new()
+                };
+                match total {
+                    Some(total) => format!(
+                        "{}: {} / {}{}",
+                        phase.label(),
+                        format_bytes(done),
+                        format_bytes(total),
+                        rate
+                    ),
+                    None => format!("{}: {}{}", phase.label(), format_bytes(done), rate),
+                }
+            }
+        };
+
+        eprint!("\r\x1b[2K{}", line);
+        let _ = std::io::stderr().flush();
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
@@ -1,6 +1,7 @@
 use reqwest::Url;
 use std::path::PathBuf;
 
+use crate::secret::SecretString;
 use crate::UniffiCustomTypeConverter;
 // Custom UniFFI types
 
@@ -33,3 +34,18 @@ impl UniffiCustomTypeConverter for PathBuf {
         format!("{:?}", obj.display())
     }
 }
+
+// `SecretString` as a custom type, with `String` as the Builtin
+uniffi::custom_type!(SecretString, String);
+
+impl UniffiCustomTypeConverter for SecretString {
+    type Builtin = String;
+
+    fn into_custom(val: Self::Builtin) -> uniffi::Result<Self> {
+        Ok(SecretString::new(val))
+    }
+
+    fn from_custom(obj: Self) -> Self::Builtin {
+        obj.expose_secret().to_owned()
+    }
+}
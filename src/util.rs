@@ -1,30 +1,85 @@
-use std::io::IsTerminal;
-use std::process::exit;
-use std::sync::Mutex;
+use crate::error::{PasteError, PbResult};
 
-pub fn check_filesize(len: u64, opts_size_limt: Option<u64>) {
-    static MUTEX_IS_CONFIRMED: Mutex<bool> = Mutex::new(false);
-    let mut user_confirmed_size = MUTEX_IS_CONFIRMED.lock().unwrap();
+/// Asked before posting a paste or comment which exceeds the configured size limit
+#[cfg_attr(feature = "uniffi", uniffi::export(with_foreign))]
+pub trait SizeLimitConfirmation: Send + Sync {
+    /// Return true to post it anyway
+    fn confirm_size(&self, size: u64, limit: u64) -> bool;
+}
 
-    if *user_confirmed_size {
-        return;
+/// Check a size against the size limit. Exceeding it fails with `PasteError::SizeLimitExceeded`,
+/// unless the confirmation callback accepts it.
+pub fn check_filesize(
+    len: u64,
+    size_limit: Option<u64>,
+    confirmation: Option<&dyn SizeLimitConfirmation>,
+) -> PbResult<()> {
+    match size_limit {
+        Some(limit) if len > limit => {
+            if confirmation.is_some_and(|confirmation| confirmation.confirm_size(len, limit)) {
+                Ok(())
+            } else {
+                Err(PasteError::SizeLimitExceeded { size: len, limit })
+            }
+        }
+        _ => Ok(()),
     }
+}
 
-    if let Some(size_limit) = opts_size_limt {
-        if len > size_limit {
-            if !std::io::stdin().is_terminal() {
-                exit(1)
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
 
-            let confirmation = dialoguer::Confirm::new()
-                .with_prompt("This paste exceeds your defined size limit. Continue?")
-                .interact()
-                .unwrap();
+    struct Confirmation {
+        answer: bool,
+        asked: AtomicBool,
+    }
 
-            if !confirmation {
-                exit(1)
+    impl Confirmation {
+        fn answering(answer: bool) -> Self {
+            Self {
+                answer,
+                asked: AtomicBool::new(false),
             }
-            *user_confirmed_size = true;
         }
     }
+
+    impl SizeLimitConfirmation for Confirmation {
+        fn confirm_size(&self, size: u64, limit: u64) -> bool {
+            assert_eq!((size, limit), (11, 10));
+            self.asked.store(true, Ordering::SeqCst);
+            self.answer
+        }
+    }
+
+    #[test]
+    fn sizes_within_the_limit_pass_without_asking() {
+        let confirmation = Confirmation::answering(false);
+        check_filesize(10, Some(10), Some(&confirmation)).unwrap();
+        check_filesize(u64::MAX, None, Some(&confirmation)).unwrap();
+        assert!(!confirmation.asked.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn sizes_over_the_limit_need_confirmation() {
+        assert!(matches!(
+            check_filesize(11, Some(10), None),
+            Err(PasteError::SizeLimitExceeded {
+                size: 11,
+                limit: 10
+            })
+        ));
+
+        let refuse = Confirmation::answering(false);
+        assert!(matches!(
+            check_filesize(11, Some(10), Some(&refuse)),
+            Err(PasteError::SizeLimitExceeded { .. })
+        ));
+        assert!(refuse.asked.load(Ordering::SeqCst));
+
+        let accept = Confirmation::answering(true);
+        check_filesize(11, Some(10), Some(&accept)).unwrap();
+        assert!(accept.asked.load(Ordering::SeqCst));
+    }
 }
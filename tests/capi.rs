@@ -0,0 +1,62 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// target/debug or target/release, where `cargo build` puts the cdylib
+fn target_dir() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    exe.parent().unwrap().parent().unwrap().to_path_buf()
+}
+
+fn compile(args: &[&std::ffi::OsStr]) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+    let status = Command::new(compiler)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-pedantic"])
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg(root.join("examples/c/pbcli_example.c"))
+        .args(args)
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success(), "compiling the C example failed");
+}
+
+#[test]
+#[cfg(unix)]
+fn c_example_compiles() {
+    let object = target_dir().join("pbcli_example.o");
+    compile(&["-c".as_ref(), "-o".as_ref(), object.as_os_str()]);
+}
+
+/// `cargo test` does not build the cdylib, so build it into the target directory first
+#[cfg(target_os = "linux")]
+fn build_cdylib(target: &Path) {
+    let mut cargo = Command::new(env!("CARGO"));
+    cargo
+        .args(["build", "--lib", "--manifest-path"])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"));
+    if target.ends_with("release") {
+        cargo.arg("--release");
+    }
+    let status = cargo.status().expect("failed to run cargo");
+    assert!(status.success(), "building libpbcli failed");
+    assert!(
+        target.join("libpbcli.so").exists(),
+        "libpbcli.so was not built"
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn c_example_links() {
+    let target = target_dir();
+    build_cdylib(&target);
+    let output = target.join("pbcli_example");
+    compile(&[
+        "-L".as_ref(),
+        target.as_os_str(),
+        "-lpbcli".as_ref(),
+        "-o".as_ref(),
+        output.as_os_str(),
+    ]);
+}